@@ -31,6 +31,26 @@ impl Cache {
     self.cache.lock().expect("cache lock").clear();
   }
 
+  /// Invalidate a single cache entry, if present.
+  pub fn invalidate(&self, key: &str) {
+    if self.cache.lock().expect("cache lock").remove(key).is_some() {
+      log::debug!("invalidating cache entry: {}", key);
+    }
+  }
+
+  /// Invalidate every cache entry whose key starts with `prefix`.
+  pub fn invalidate_prefix(&self, prefix: &str) {
+    self.cache.lock().expect("cache lock").retain(|key, _| {
+      let retain = !key.starts_with(prefix);
+
+      if !retain {
+        log::debug!("invalidating cache entry: {}", key);
+      }
+
+      retain
+    });
+  }
+
   /// Get a cached entry, if any, or compute it and cache it.
   pub fn cache(&self, key: &str, gen: impl FnOnce() -> String) -> String {
     let current = {
@@ -26,14 +26,123 @@ pub struct Config {
   /// Port to listen on.
   pub port: u16,
 
+  /// Public URL of the website, e.g. `https://this-week-in-neovim.org`; used to build absolute links.
+  #[serde(default = "default_base_url")]
+  pub base_url: String,
+
   /// Path where to read the weekly contents.
+  ///
+  /// It is either a directory, or a tar archive of one, which is read again whenever it changes.
   pub news_root: PathBuf,
 
+  /// Git revision to read the weekly contents from, e.g. `refs/heads/master`.
+  ///
+  /// If set, `news_root` must be in a git repository, and news are read from the revision instead of the working tree.
+  #[serde(default)]
+  pub git_revision: Option<String>,
+
   /// Static directory (CSS, etc.).
   pub static_dir: PathBuf,
+
+  /// Path where to persist the numbers assigned to weekly news.
+  ///
+  /// If not set, numbers are assigned in memory only, from the oldest to the most recent news.
+  #[serde(default)]
+  pub numbers_path: Option<PathBuf>,
+
+  /// Path where to persist a snapshot of the parsed weekly news.
+  ///
+  /// If set, news are served from the snapshot right away when starting, and only the news that changed since are
+  /// parsed again.
+  #[serde(default)]
+  pub snapshot_path: Option<PathBuf>,
+
+  /// Shorthands turned into links in weekly news.
+  ///
+  /// If set, they replace the default ones (Neovim pull requests and issues, plugin repositories).
+  #[serde(default)]
+  pub autolinks: Option<Vec<AutolinkConfig>>,
+
+  /// Theme used to highlight code blocks in weekly news.
+  ///
+  /// If not set, a default dark theme is used.
+  #[serde(default)]
+  pub highlight_theme: Option<String>,
+
+  /// Secret giving editors access to drafts and scheduled news, under `/preview`.
+  ///
+  /// If not set, previews are disabled.
+  #[serde(default)]
+  pub preview_secret: Option<String>,
+
+  /// Secret giving access to the report of the contents that couldn’t be loaded, under `/diagnostics`.
+  ///
+  /// If not set, the report is disabled.
+  #[serde(default)]
+  pub diagnostics_secret: Option<String>,
+
+  /// Metadata of the feeds (RSS, Atom and JSON Feed).
+  #[serde(default)]
+  pub feed: FeedConfig,
+
+  /// Content of `/robots.txt`.
+  ///
+  /// If not set, every page is allowed except previews and diagnostics, and the sitemap is advertised.
+  #[serde(default)]
+  pub robots_txt: Option<String>,
+}
+
+fn default_base_url() -> String {
+  "https://this-week-in-neovim.org".to_owned()
+}
+
+/// Metadata of the feeds.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default)]
+pub struct FeedConfig {
+  pub title: String,
+  pub description: String,
+
+  /// Language of the news, e.g. `en-us`.
+  pub language: Option<String>,
+
+  /// Email address of the person responsible for the news, e.g. `editor@example.com (Jane Doe)`.
+  ///
+  /// It is also used as the author of the news that don’t list any; it defaults to the editor of the original news.
+  pub managing_editor: Option<String>,
+
+  /// URL of an image (GIF, JPEG or PNG) representing the feeds.
+  pub image: Option<String>,
+}
+
+impl Default for FeedConfig {
+  fn default() -> Self {
+    Self {
+      title: "This Week In Neovim".to_owned(),
+      description: "Weekly news about Neovim and its plugin ecosystem".to_owned(),
+      language: Some("en".to_owned()),
+      managing_editor: Some("dimitri.sabadie@gmail.com (Dimitri 'phaazon' Sabadie)".to_owned()),
+      image: None,
+    }
+  }
+}
+
+/// A shorthand turned into a link in weekly news.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct AutolinkConfig {
+  /// Regular expression matching the shorthand.
+  pub pattern: String,
+
+  /// URL of the link; it can refer to the capture groups of the pattern with `$name`.
+  pub url: String,
 }
 
 impl Config {
+  /// Absolute URL of a path of the website; `path` must start with a `/`.
+  pub fn site_url(&self, path: &str) -> String {
+    format!("{}{}", self.base_url.trim_end_matches('/'), path)
+  }
+
   pub fn load() -> Result<Self, ConfigError> {
     println!("loading configuration");
 
@@ -3,19 +3,39 @@ use std::fmt::Display;
 use chrono::{Datelike as _, Utc};
 
 pub fn html_wrap(title: impl Into<String>, contents: impl Display) -> String {
+  html_wrap_with_canonical(title, None, contents)
+}
+
+/// Same as [`html_wrap`], declaring the canonical URL of the page, if any.
+pub fn html_wrap_with_canonical(
+  title: impl Into<String>,
+  canonical: Option<&str>,
+  contents: impl Display,
+) -> String {
   let title = title.into();
+  let canonical = canonical.map_or_else(String::new, |url| {
+    format!(r#"<link rel="canonical" href="{}" />"#, escape(url))
+  });
   let now = Utc::now().year();
 
   let title = if title.is_empty() {
     title
   } else {
-    format!(" — {}", title)
+    format!(" — {}", escape(&title))
   };
 
   format!(
     include_str!("html_wrapper.html"),
     title = title,
+    canonical = canonical,
     contents = contents,
     now = now
   )
 }
+
+/// Escape text so that it can be safely inserted in HTML.
+pub fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  pulldown_cmark::escape::escape_html(&mut escaped, text).expect("HTML escaping");
+  escaped
+}
@@ -3,7 +3,8 @@ mod config;
 mod html_wrapper;
 mod routes;
 
-use crate::{cache::Cache, config::Config};
+use crate::{cache::Cache, config::Config, routes::preview::PreviewSecret};
+use chrono::Utc;
 use notify::Watcher;
 use rocket::{
   catchers,
@@ -14,39 +15,134 @@ use rocket::{
 };
 use std::{
   net::{IpAddr, Ipv4Addr},
+  path::Path,
   process::exit,
   sync::mpsc,
   thread,
   time::Duration,
 };
-use twin::news::NewsState;
+use twin::{
+  autolink::{AutolinkRule, Autolinks},
+  git::GitRevision,
+  highlight::Highlighter,
+  news::{LoadMode, NewsState, NewsStore, ParseOptions},
+  numbers::IssueNumbers,
+};
 
 #[cfg(debug_assertions)]
 const CACHE_TTL: Duration = Duration::from_secs(5); // 5s of TTL
 #[cfg(not(debug_assertions))]
 const CACHE_TTL: Duration = Duration::from_secs(3600 * 24); // 1 day of TTL
 
+/// Maximum time between two checks of the news scheduled for publication.
+///
+/// News are normally published right on time; this is only a safety net, e.g. if the clock of the system jumps.
+const PUBLICATION_POLL: Duration = Duration::from_secs(60);
+
+/// Time between two checks of the git revision news are read from, if any.
+const GIT_POLL: Duration = Duration::from_secs(30);
+
+#[allow(clippy::field_reassign_with_default)]
+fn rocket_config(config: &Config) -> rocket::Config {
+  let mut rocket_config = rocket::Config::default();
+  rocket_config.address = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+  rocket_config.port = config.port;
+  // debug logs show request headers and forms, which carry the secrets of previews and diagnostics
+  rocket_config.log_level = LogLevel::Normal;
+  rocket_config
+}
+
 #[launch]
 fn rocket() -> _ {
   match Config::load() {
     Ok(config) => {
-      let mut rocket_config = rocket::Config::default();
-      rocket_config.address = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
-      rocket_config.port = config.port;
-      rocket_config.log_level = LogLevel::Debug;
+      let rocket_config = rocket_config(&config);
 
       let cache = Cache::new(CACHE_TTL);
       cache.schedule_eviction();
 
+      let numbers = match config.numbers_path {
+        Some(ref path) => IssueNumbers::load(path).unwrap_or_else(|err| {
+          eprintln!("cannot start: cannot load issue numbers: {}", err);
+          exit(1)
+        }),
+        None => IssueNumbers::default(),
+      };
+
+      let mut parse_options = ParseOptions::default();
+
+      if let Some(ref autolinks) = config.autolinks {
+        let rules = autolinks
+          .iter()
+          .map(|autolink| AutolinkRule::new(&autolink.pattern, &autolink.url))
+          .collect::<Result<_, _>>()
+          .unwrap_or_else(|err| {
+            eprintln!("cannot start: invalid autolink pattern: {}", err);
+            exit(1)
+          });
+        parse_options.autolinks = Autolinks::new(rules);
+      }
+
+      if let Some(ref theme) = config.highlight_theme {
+        let highlighter = Highlighter::new(theme).unwrap_or_else(|| {
+          let themes: Vec<_> = Highlighter::themes().collect();
+          eprintln!(
+            "cannot start: unknown highlighting theme {}; available themes: {}",
+            theme,
+            themes.join(", ")
+          );
+          exit(1)
+        });
+        parse_options.highlighter = Some(highlighter);
+      }
+
+      let mut store = NewsStore::open(&config.news_root)
+        .unwrap_or_else(|err| {
+          eprintln!(
+            "cannot start: cannot open {}: {}",
+            config.news_root.display(),
+            err
+          );
+          exit(1)
+        })
+        .with_numbers(numbers)
+        .with_load_mode(LoadMode::Lenient)
+        .with_parse_options(parse_options);
+
+      if let Some(ref revision) = config.git_revision {
+        let git = GitRevision::new(&config.news_root, revision).unwrap_or_else(|err| {
+          eprintln!(
+            "cannot start: cannot read git revision {}: {}",
+            revision, err
+          );
+          exit(1)
+        });
+        store = store.with_source(git);
+      }
+
+      if let Some(ref path) = config.snapshot_path {
+        store = store.with_snapshot(path);
+
+        // serve the news of the snapshot as soon as the server starts; the contents are read again after launch
+        match store.restore_snapshot() {
+          Ok(true) => println!("restored news snapshot {}", path.display()),
+          Ok(false) => (),
+          Err(err) => eprintln!("cannot restore news snapshot {}: {}", path.display(), err),
+        }
+      }
+
       let (ignition_tx, ignition_rx) = mpsc::sync_channel(0);
-      let state = NewsState::new(&config.news_root);
+      let state = NewsState::from_store(store);
       run_state(ignition_rx, &config, cache.clone(), state.clone());
 
-      let static_fs = FileServer::new(config.static_dir, Options::default());
+      let preview = PreviewSecret(config.preview_secret.clone());
+      let static_fs = FileServer::new(&config.static_dir, Options::default());
 
       rocket::custom(rocket_config)
         .manage(state)
         .manage(cache)
+        .manage(preview)
+        .manage(config)
         .register("/", catchers![routes::not_found::not_found])
         .attach(AdHoc::on_liftoff("state_sync", move |_| {
           Box::pin(async move {
@@ -71,20 +167,83 @@ fn run_state(ignition_rx: mpsc::Receiver<()>, config: &Config, cache: Cache, sta
       .recv_timeout(Duration::from_secs(5))
       .expect("timeout while waiting for rocket to launch");
 
-    if let Err(err) = state
+    reload_all(&state, &cache);
+    schedule_publications(cache.clone(), state.clone());
+
+    match config.git_revision {
+      Some(ref revision) => watch_revision(&config, revision, cache, state),
+      None => watch_state(&config, cache, state),
+    }
+  });
+}
+
+/// Reload everything when the git revision news are read from points to another commit.
+fn watch_revision(config: &Config, revision: &str, cache: Cache, state: NewsState) {
+  let git = match GitRevision::new(&config.news_root, revision) {
+    Ok(git) => git,
+    Err(err) => {
+      log::error!("cannot watch git revision {}: {}", revision, err);
+      return;
+    }
+  };
+
+  log::debug!("watching git revision {}", revision);
+
+  loop {
+    thread::sleep(GIT_POLL);
+
+    let commit = match git.commit_id() {
+      Ok(commit) => commit,
+      Err(err) => {
+        log::error!("cannot resolve git revision {}: {}", revision, err);
+        continue;
+      }
+    };
+
+    if state.news_store().source_version().as_deref() != Some(commit.as_str()) {
+      log::info!("git revision moved to {}", commit);
+      reload_all(&state, &cache);
+    }
+  }
+}
+
+/// Publish the scheduled news when their time comes.
+fn schedule_publications(cache: Cache, state: NewsState) {
+  let _ = thread::spawn(move || loop {
+    let delay = state
       .news_store()
-      .write()
-      .expect("news store")
-      .populate_from_root()
-    {
-      log::error!(
-        "cannot populate from root ({}): {}",
-        config.news_root.display(),
-        err
-      );
+      .next_publication()
+      .map_or(PUBLICATION_POLL, |publish_at| {
+        (publish_at - Utc::now())
+          .to_std()
+          .unwrap_or(Duration::ZERO)
+          .min(PUBLICATION_POLL)
+      });
+    thread::sleep(delay);
+
+    let due = state
+      .news_store()
+      .next_publication()
+      .is_some_and(|publish_at| publish_at <= Utc::now());
+    if !due {
+      continue;
     }
 
-    watch_state(&config, cache, state);
+    let mut published = Vec::new();
+    let result = state.update(|store| {
+      published = store.publish_due(Utc::now());
+      Ok(())
+    });
+
+    match result {
+      Ok(store) => {
+        for key in published {
+          routes::invalidate_news(&cache, &store, key);
+        }
+      }
+
+      Err(err) => log::error!("cannot publish scheduled news: {}", err),
+    }
   });
 }
 
@@ -101,23 +260,60 @@ fn watch_state(config: &Config, cache: Cache, state: NewsState) {
     log::debug!("event: {:?}", event);
 
     match event {
-      notify::DebouncedEvent::Create(_path) | notify::DebouncedEvent::Write(_path) => {
-        cache.invalidate_all();
-
-        // FIXME: suboptimal; we should be parsing path and use NewsStore::update instead of recomputing everything
-        if let Err(err) = state
-          .news_store()
-          .write()
-          .expect("news store")
-          .populate_from_root()
-        {
-          log::error!("cannot repopulate news: {}", err);
+      notify::DebouncedEvent::Create(path)
+      | notify::DebouncedEvent::Write(path)
+      | notify::DebouncedEvent::Remove(path) => refresh_path(&state, &cache, &path),
+
+      notify::DebouncedEvent::Rename(from_path, to_path) => {
+        let store = state.news_store();
+
+        match (
+          store.key_from_path(&from_path),
+          store.key_from_path(&to_path),
+        ) {
+          (Some(from), Some(to)) if from != to => {
+            match state.update(|store| store.rename(from, to)) {
+              Ok(store) => {
+                routes::invalidate_news(&cache, &store, from);
+                routes::invalidate_news(&cache, &store, to);
+              }
+
+              Err(err) => log::error!("cannot rename news {:?} to {:?}: {}", from, to, err),
+            }
+          }
+
+          _ => {
+            refresh_path(&state, &cache, &from_path);
+            refresh_path(&state, &cache, &to_path);
+          }
         }
       }
 
+      notify::DebouncedEvent::Rescan => reload_all(&state, &cache),
+
       _ => (),
     }
   }
 
   log::debug!("watch state exited");
 }
+
+/// Synchronize the news a path belongs to, or everything if the path is not part of a single news.
+fn refresh_path(state: &NewsState, cache: &Cache, path: &Path) {
+  match state.news_store().key_from_path(path) {
+    Some(key) => match state.update(|store| store.refresh(key)) {
+      Ok(store) => routes::invalidate_news(cache, &store, key),
+      Err(err) => log::error!("cannot refresh news {:?}: {}", key, err),
+    },
+
+    // not part of a single weekly news (e.g. a year or month directory); reload everything
+    None => reload_all(state, cache),
+  }
+}
+
+fn reload_all(state: &NewsState, cache: &Cache) {
+  match state.reload() {
+    Ok(_) => cache.invalidate_all(),
+    Err(err) => log::error!("cannot repopulate news: {}", err),
+  }
+}
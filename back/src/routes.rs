@@ -1,11 +1,70 @@
+use crate::cache::Cache;
 use rocket::{routes, Route};
+use twin::news::{NewsKey, NewsStore};
 
 pub mod all;
+pub mod api;
+pub mod diagnostics;
+pub mod feeds;
 pub mod home;
 pub mod not_found;
+pub mod plugins;
+pub mod preview;
 pub mod rss;
+pub mod search;
+pub mod secret;
+pub mod sitemap;
 pub mod week;
 
 pub fn routes() -> Vec<Route> {
-  routes![all::all, home::home, week::by_key, week::latest, rss::rss]
+  routes![
+    all::all,
+    api::by_key,
+    api::issues,
+    api::latest,
+    api::page,
+    diagnostics::diagnostics,
+    diagnostics::unlock,
+    diagnostics::unlock_form,
+    feeds::atom,
+    feeds::json_feed,
+    home::home,
+    plugins::plugins,
+    plugins::plugin,
+    preview::asset,
+    preview::by_key,
+    preview::list,
+    preview::unlock,
+    preview::unlock_form,
+    week::asset,
+    week::by_key,
+    week::by_number,
+    week::latest,
+    rss::rss,
+    rss::section,
+    rss::tag,
+    search::search,
+    sitemap::robots,
+    sitemap::sitemap
+  ]
+}
+
+/// Invalidate every cached page depending on the weekly news identified by `key`.
+///
+/// That includes the listings, the feeds, the plugin pages, the page of the news and the pages of its neighbours, as
+/// their prev / next links might have changed.
+pub fn invalidate_news(cache: &Cache, store: &NewsStore, key: NewsKey) {
+  cache.invalidate("/home");
+  cache.invalidate("/all");
+  cache.invalidate_prefix("/rss");
+  cache.invalidate("/atom.xml");
+  cache.invalidate("/feed.json");
+  cache.invalidate("/sitemap.xml");
+  cache.invalidate_prefix("/plugins");
+  cache.invalidate(&week::cache_key(&key));
+
+  let (prev, next) = store.neighbours(&key);
+  for neighbour in prev.iter().chain(next.iter()) {
+    cache.invalidate(&week::cache_key(neighbour));
+  }
 }
@@ -0,0 +1,261 @@
+//! Versioned JSON API, for dashboards, editor plugins, etc.
+//!
+//! Everything is built from the current generation of the store, so responses are never cached.
+
+use crate::routes::{
+  home,
+  week::{self, MonthParam},
+};
+use chrono::{DateTime, Utc};
+use rocket::{get, response::status::NotFound, serde::json::Json, State};
+use serde::Serialize;
+use twin::{
+  issue::TocEntry,
+  news::{LatestNews, NewsKey, NewsMetadata, NewsState, NewsStore},
+};
+
+/// Number of issues per page when not specified.
+const DEFAULT_PER_PAGE: usize = 20;
+
+/// Maximum number of issues per page.
+const MAX_PER_PAGE: usize = 100;
+
+/// A weekly news, as listed.
+#[derive(Debug, Serialize)]
+pub struct IssueSummary {
+  pub key: NewsKey,
+  pub number: Option<u32>,
+  pub title: String,
+  pub url: String,
+}
+
+/// A single weekly news, with its contents.
+#[derive(Debug, Serialize)]
+pub struct IssueDetails {
+  #[serde(flatten)]
+  pub summary: IssueSummary,
+  pub html: String,
+  pub toc: Vec<TocEntry>,
+  pub metadata: NewsMetadata,
+  pub last_updated: Option<DateTime<Utc>>,
+  pub prev: Option<NewsKey>,
+  pub next: Option<NewsKey>,
+}
+
+/// A range of weekly news, from the most recent ones.
+#[derive(Debug, Serialize)]
+pub struct IssuePage {
+  /// Page number, starting at 1.
+  pub page: usize,
+  pub per_page: usize,
+
+  /// Total number of issues and pages.
+  pub total: usize,
+  pub pages: usize,
+
+  pub issues: Vec<IssueSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiError {
+  pub error: String,
+}
+
+type ApiResult<T> = Result<Json<T>, NotFound<Json<ApiError>>>;
+
+/// All the weekly news, from the most recent one.
+#[get("/api/v1/issues")]
+pub fn issues(state: &State<NewsState>) -> Json<Vec<IssueSummary>> {
+  let store = state.news_store();
+  let issues = store
+    .keys()
+    .rev()
+    .map(|key| summary(&store, *key))
+    .collect();
+
+  Json(issues)
+}
+
+/// A page of weekly news, from the most recent ones.
+#[get("/api/v1/issues/page/<page>?<per_page>")]
+pub fn page(
+  page: usize,
+  per_page: Option<usize>,
+  state: &State<NewsState>,
+) -> ApiResult<IssuePage> {
+  let store = state.news_store();
+  let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+  let keys: Vec<_> = store.keys().rev().copied().collect();
+  let total = keys.len();
+  let pages = total.div_ceil(per_page);
+
+  // the first page always exists, even if there is no issue at all
+  if page == 0 || page > pages.max(1) {
+    return Err(not_found(format!("page {page} doesn’t exist")));
+  }
+
+  let issues = keys
+    .into_iter()
+    .skip((page - 1) * per_page)
+    .take(per_page)
+    .map(|key| summary(&store, key))
+    .collect();
+
+  Ok(Json(IssuePage {
+    page,
+    per_page,
+    total,
+    pages,
+    issues,
+  }))
+}
+
+/// The most recent weekly news.
+#[get("/api/v1/issues/latest")]
+pub fn latest(state: &State<NewsState>) -> ApiResult<LatestNews> {
+  let store = state.news_store();
+  let (key, news) = store
+    .keys()
+    .next_back()
+    .and_then(|key| Some((*key, store.get(key)?.clone())))
+    .ok_or_else(|| not_found("no latest news available".to_owned()))?;
+
+  Ok(Json(LatestNews { key, news }))
+}
+
+/// A single weekly news.
+#[get("/api/v1/issues/<year>/<month>/<day>")]
+pub fn by_key(
+  year: u16,
+  month: MonthParam,
+  day: u8,
+  state: &State<NewsState>,
+) -> ApiResult<IssueDetails> {
+  let MonthParam(month) = month;
+  let key = NewsKey { year, month, day };
+  let store = state.news_store();
+  let news = store
+    .get(&key)
+    .ok_or_else(|| not_found(format!("news {year}-{month}-{day} doesn’t exist")))?;
+
+  Ok(Json(IssueDetails {
+    summary: summary(&store, key),
+    html: news.html.clone(),
+    toc: news.toc.clone(),
+    metadata: news.metadata.clone(),
+    last_updated: news.last_updated,
+    prev: news.prev,
+    next: news.next,
+  }))
+}
+
+fn summary(store: &NewsStore, key: NewsKey) -> IssueSummary {
+  IssueSummary {
+    key,
+    number: store.number(&key),
+    title: home::news_title(store, &key),
+    url: week::canonical_path(&key),
+  }
+}
+
+fn not_found(error: String) -> NotFound<Json<ApiError>> {
+  NotFound(Json(ApiError { error }))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rocket::{http::Status, local::blocking::Client, routes, serde::json::Value};
+  use twin::news::{MemorySource, NewsStore};
+
+  fn client(source: MemorySource) -> Client {
+    let mut store = NewsStore::new("contents").with_source(source);
+    store.populate_from_root().unwrap();
+
+    let rocket = rocket::build()
+      .manage(NewsState::from_store(store))
+      .mount("/", routes![by_key, issues, latest, page]);
+    Client::tracked(rocket).expect("valid rocket instance")
+  }
+
+  fn get(client: &Client, uri: &str) -> (Status, Value) {
+    let response = client.get(uri).dispatch();
+    (response.status(), response.into_json().unwrap())
+  }
+
+  #[test]
+  fn test_empty_store() {
+    let client = client(MemorySource::new());
+
+    assert_eq!(
+      get(&client, "/api/v1/issues"),
+      (Status::Ok, Value::Array(Vec::new()))
+    );
+    assert_eq!(get(&client, "/api/v1/issues/latest").0, Status::NotFound);
+
+    // the first page always exists
+    let (status, page) = get(&client, "/api/v1/issues/page/1");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(page["total"], 0);
+    assert_eq!(page["pages"], 0);
+    assert_eq!(get(&client, "/api/v1/issues/page/2").0, Status::NotFound);
+  }
+
+  #[test]
+  fn test_issues() {
+    let client = client(
+      MemorySource::new()
+        .with_file("2022/Jun/06.md", "# First")
+        .with_file("2022/Jun/13.md", "# Second")
+        .with_file("2022/Jun/20.md", "+++\ntitle = \"Third\"\n+++\n# Third"),
+    );
+
+    let (status, issues) = get(&client, "/api/v1/issues");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(issues.as_array().unwrap().len(), 3);
+    assert_eq!(issues[0]["url"], "/2022/Jun/20");
+
+    let (status, latest) = get(&client, "/api/v1/issues/latest");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(latest["news"]["metadata"]["title"], "Third");
+
+    let (status, issue) = get(&client, "/api/v1/issues/2022/Jun/6");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(issue["number"], 1);
+    assert_eq!(issue["next"]["day"], 13);
+    assert_eq!(
+      get(&client, "/api/v1/issues/2022/Jun/27").0,
+      Status::NotFound
+    );
+  }
+
+  #[test]
+  fn test_pages() {
+    let client = client(
+      MemorySource::new()
+        .with_file("2022/Jun/06.md", "# First")
+        .with_file("2022/Jun/13.md", "# Second")
+        .with_file("2022/Jun/20.md", "# Third"),
+    );
+
+    let (status, page) = get(&client, "/api/v1/issues/page/2?per_page=2");
+    assert_eq!(status, Status::Ok);
+    assert_eq!(page["pages"], 2);
+    assert_eq!(page["issues"].as_array().unwrap().len(), 1);
+    assert_eq!(page["issues"][0]["url"], "/2022/Jun/06");
+
+    assert_eq!(get(&client, "/api/v1/issues/page/0").0, Status::NotFound);
+    assert_eq!(
+      get(&client, "/api/v1/issues/page/3?per_page=2").0,
+      Status::NotFound
+    );
+
+    // per_page is clamped
+    let (_, page) = get(&client, "/api/v1/issues/page/3?per_page=0");
+    assert_eq!(page["per_page"], 1);
+    assert_eq!(page["pages"], 3);
+    let (_, page) = get(&client, "/api/v1/issues/page/1?per_page=1000");
+    assert_eq!(page["per_page"], MAX_PER_PAGE);
+    assert_eq!(page["issues"].as_array().unwrap().len(), 3);
+  }
+}
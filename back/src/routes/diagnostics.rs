@@ -0,0 +1,89 @@
+use crate::{
+  config::Config,
+  html_wrapper::{escape, html_wrap},
+  routes::secret::{self, SecretForm},
+};
+use rocket::{
+  form::Form,
+  get,
+  http::{CookieJar, Status},
+  post,
+  request::{FromRequest, Outcome, Request},
+  response::{content::RawHtml, Redirect},
+  State,
+};
+use twin::news::NewsState;
+
+/// Guard of the diagnostics: the request must carry the diagnostics secret.
+pub struct DiagnosticsAccess;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for DiagnosticsAccess {
+  type Error = ();
+
+  async fn from_request(request: &'r Request<'_>) -> Outcome<Self, ()> {
+    let expected = request
+      .rocket()
+      .state::<Config>()
+      .and_then(|config| config.diagnostics_secret.as_deref());
+
+    match secret::check_request(request, expected) {
+      Some(()) => Outcome::Success(DiagnosticsAccess),
+      None => Outcome::Forward(Status::NotFound),
+    }
+  }
+}
+
+/// Report of the entries of the contents that couldn’t be loaded.
+///
+/// The report shows paths on the server and raw errors, so it is only available to editors knowing the secret set in
+/// the configuration (see [`crate::routes::secret`]); without a secret, it is disabled. This page is never cached, so
+/// that it always reflects the current state of the store.
+#[get("/diagnostics")]
+pub fn diagnostics(_access: DiagnosticsAccess, state: &State<NewsState>) -> RawHtml<String> {
+  let store = state.news_store();
+  let report = store.report();
+
+  let failures: Vec<_> = report
+    .failures
+    .iter()
+    .map(|failure| {
+      format!(
+        "<tr><td><code>{path}</code></td><td>{kind}</td><td>{message}</td></tr>",
+        path = escape(&failure.path.display().to_string()),
+        kind = failure.error.kind(),
+        message = escape(&failure.error.to_string()),
+      )
+    })
+    .collect();
+
+  let html = format!(
+    include_str!("diagnostics.html"),
+    news_len = store.keys().count(),
+    failures_len = failures.len(),
+    failures = failures.join("")
+  );
+
+  RawHtml(html_wrap("Diagnostics", html))
+}
+
+/// Form asking for the diagnostics secret, when it is missing or wrong.
+#[get("/diagnostics", rank = 2)]
+pub fn unlock_form(config: &State<Config>) -> Option<RawHtml<String>> {
+  secret::form(
+    "/diagnostics",
+    "Diagnostics",
+    config.diagnostics_secret.as_deref(),
+  )
+}
+
+/// Remember the diagnostics secret submitted by an editor.
+#[post("/diagnostics", data = "<form>")]
+pub fn unlock(form: Form<SecretForm>, config: &State<Config>, cookies: &CookieJar<'_>) -> Redirect {
+  secret::unlock(
+    "/diagnostics",
+    config.diagnostics_secret.as_deref(),
+    form,
+    cookies,
+  )
+}
@@ -0,0 +1,368 @@
+//! Atom and JSON Feed outputs, built from the same entries.
+//!
+//! See [`crate::routes::rss`] for the RSS feed.
+
+use crate::{cache::Cache, config::Config, html_wrapper::escape, routes::week};
+use chrono::{DateTime, TimeZone as _, Utc};
+use rocket::{get, http::ContentType, State};
+use serde::Serialize;
+use twin::{
+  issue::IssuePart,
+  news::{News, NewsKey, NewsState, NewsStore},
+};
+
+/// A weekly news, as found in feeds.
+#[derive(Debug)]
+pub struct FeedEntry {
+  /// Stable identifier of the entry; it is the URL of the news, which never changes, and the topic the entry is
+  /// restricted to, if any.
+  pub id: String,
+  pub url: String,
+  pub title: String,
+  pub summary: Option<String>,
+  pub authors: Vec<String>,
+  pub tags: Vec<String>,
+  pub content_html: String,
+  pub published: DateTime<Utc>,
+  pub updated: DateTime<Utc>,
+}
+
+impl FeedEntry {
+  /// News that don’t list any author are attributed to the managing editor, if any.
+  pub fn new(config: &Config, key: &NewsKey, news: &News) -> Self {
+    let url = news_url(config, key);
+    let metadata = &news.metadata;
+    let authors = if metadata.authors.is_empty() {
+      config.feed.managing_editor.iter().cloned().collect()
+    } else {
+      metadata.authors.clone()
+    };
+    let published = published_date(key, news);
+    let updated = updated_date(key, news);
+
+    Self {
+      id: url.clone(),
+      url,
+      title: metadata
+        .title
+        .clone()
+        .unwrap_or_else(|| format!("{} {} {}", key.day, key.month, key.year)),
+      summary: metadata.summary.clone(),
+      authors,
+      tags: metadata.tags.clone(),
+      content_html: news.html.clone(),
+      published,
+      updated,
+    }
+  }
+
+  /// Restrict the entry to some parts of its news about a topic, such as a section; the entry is named after the
+  /// first part.
+  ///
+  /// The entry then points to the first part, and gets its own identifier, made of the topic; unlike the anchor of the
+  /// part, it doesn’t change when its heading is edited.
+  fn restricted_to(mut self, topic: &str, parts: &[IssuePart]) -> Option<Self> {
+    let first = parts.first()?.heading();
+    self.id = format!("{}#{}", self.id, topic);
+    self.url = format!("{}#{}", self.url, first.id);
+    self.title = format!("{} — {}", self.title, first.text);
+    self.content_html = parts.iter().map(IssuePart::to_html).collect();
+
+    Some(self)
+  }
+}
+
+/// Entries of all the published news, from the most recent one.
+pub fn feed_entries(config: &Config, store: &NewsStore) -> Vec<FeedEntry> {
+  store
+    .keys()
+    .rev()
+    .filter_map(|key| Some(FeedEntry::new(config, key, store.get(key)?)))
+    .collect()
+}
+
+/// Entries restricted to the sections about a topic, such as `core` or `plugins`.
+///
+/// News without such sections are skipped. See [`twin::issue::Issue::sections_about`].
+pub fn section_entries(config: &Config, store: &NewsStore, topic: &str) -> Vec<FeedEntry> {
+  store
+    .keys()
+    .rev()
+    .filter_map(|key| {
+      let news = store.get(key)?;
+      let parts: Vec<_> = news
+        .issue
+        .sections_about(topic)
+        .into_iter()
+        .map(IssuePart::Section)
+        .collect();
+
+      FeedEntry::new(config, key, news).restricted_to(topic, &parts)
+    })
+    .collect()
+}
+
+/// Entries about a tag: whole news having the tag in their metadata, or only the parts of news tagged with it.
+///
+/// See [`twin::issue::Issue::parts_tagged`].
+pub fn tag_entries(config: &Config, store: &NewsStore, tag: &str) -> Vec<FeedEntry> {
+  store
+    .keys()
+    .rev()
+    .filter_map(|key| {
+      let news = store.get(key)?;
+      let entry = FeedEntry::new(config, key, news);
+
+      if news
+        .metadata
+        .tags
+        .iter()
+        .any(|t| t.eq_ignore_ascii_case(tag))
+      {
+        Some(entry)
+      } else {
+        entry.restricted_to(tag, &news.issue.parts_tagged(tag))
+      }
+    })
+    .collect()
+}
+
+/// Split an author into a name and an email address.
+///
+/// Authors can be given as email addresses, optionally followed by a name, as RSS expects them (e.g.
+/// `editor@example.com (Jane Doe)`); without a name, the email address is the name. Other authors are only names.
+pub fn split_author(author: &str) -> (&str, Option<&str>) {
+  let author = author.trim();
+  let (address, name) = author
+    .split_once(char::is_whitespace)
+    .unwrap_or((author, ""));
+
+  let is_email = address
+    .split_once('@')
+    .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'));
+  if !is_email {
+    return (author, None);
+  }
+
+  let name = name
+    .trim()
+    .strip_prefix('(')
+    .and_then(|name| name.strip_suffix(')'))
+    .map(str::trim)
+    .filter(|name| !name.is_empty());
+
+  (name.unwrap_or(address), Some(address))
+}
+
+/// Absolute URL of the page of a news.
+pub fn news_url(config: &Config, key: &NewsKey) -> String {
+  config.site_url(&week::canonical_path(key))
+}
+
+/// Instant a news was published: its publication instant if scheduled, or its date (at midnight UTC).
+pub fn published_date(key: &NewsKey, news: &News) -> DateTime<Utc> {
+  if let Some(publish_at) = news.metadata.publish_at {
+    return publish_at.with_timezone(&Utc);
+  }
+
+  let date = news
+    .metadata
+    .date
+    .or_else(|| key.to_date())
+    .and_then(|date| date.and_hms_opt(0, 0, 0));
+
+  date.map_or_else(Utc::now, |date| Utc.from_utc_datetime(&date))
+}
+
+/// Instant a news was last updated: its last change, if known and after its publication, or its publication.
+pub fn updated_date(key: &NewsKey, news: &News) -> DateTime<Utc> {
+  let published = published_date(key, news);
+  news.last_updated.unwrap_or(published).max(published)
+}
+
+#[get("/atom.xml")]
+pub fn atom(
+  cache: &State<Cache>,
+  state: &State<NewsState>,
+  config: &State<Config>,
+) -> (ContentType, String) {
+  let feed = cache.cache("/atom.xml", || {
+    let store = state.news_store();
+    atom_feed(config, &feed_entries(config, &store))
+  });
+
+  (ContentType::new("application", "atom+xml"), feed)
+}
+
+#[get("/feed.json")]
+pub fn json_feed(
+  cache: &State<Cache>,
+  state: &State<NewsState>,
+  config: &State<Config>,
+) -> (ContentType, String) {
+  let feed = cache.cache("/feed.json", || {
+    let store = state.news_store();
+    let feed = JsonFeed::new(config, feed_entries(config, &store));
+    rocket::serde::json::to_string(&feed).expect("JSON feed serialization")
+  });
+
+  (ContentType::new("application", "feed+json"), feed)
+}
+
+/// Render an Atom feed.
+///
+/// The feed itself is the author of the entries that don’t list any.
+pub fn atom_feed(config: &Config, entries: &[FeedEntry]) -> String {
+  let updated = entries
+    .iter()
+    .map(|entry| entry.updated)
+    .max()
+    .unwrap_or_else(Utc::now);
+
+  let entries: Vec<_> = entries
+    .iter()
+    .map(|entry| {
+      let authors: Vec<_> = entry
+        .authors
+        .iter()
+        .map(|author| {
+          let (name, email) = split_author(author);
+          let email = email.map_or_else(String::new, |email| {
+            format!("<email>{}</email>", escape(email))
+          });
+          format!("<author><name>{}</name>{}</author>", escape(name), email)
+        })
+        .collect();
+      let categories: Vec<_> = entry
+        .tags
+        .iter()
+        .map(|tag| format!(r#"<category term="{}"/>"#, escape(tag)))
+        .collect();
+      let summary = entry.summary.as_ref().map_or_else(String::new, |summary| {
+        format!("<summary>{}</summary>", escape(summary))
+      });
+
+      format!(
+        r#"<entry><id>{id}</id><title>{title}</title><link rel="alternate" type="text/html" href="{url}"/><published>{published}</published><updated>{updated}</updated>{authors}{categories}{summary}<content type="html">{content}</content></entry>"#,
+        id = escape(&entry.id),
+        title = escape(&entry.title),
+        url = escape(&entry.url),
+        published = entry.published.to_rfc3339(),
+        updated = entry.updated.to_rfc3339(),
+        authors = authors.join(""),
+        categories = categories.join(""),
+        summary = summary,
+        content = escape(&entry.content_html),
+      )
+    })
+    .collect();
+
+  format!(
+    r#"<?xml version="1.0" encoding="utf-8"?><feed xmlns="http://www.w3.org/2005/Atom" xml:base="{home}"><id>{home}</id><title>{title}</title><subtitle>{subtitle}</subtitle><link rel="self" type="application/atom+xml" href="{self_url}"/><link rel="alternate" type="text/html" href="{home}"/><author><name>{title}</name></author>{logo}<updated>{updated}</updated>{entries}</feed>"#,
+    home = escape(&config.site_url("/")),
+    self_url = escape(&config.site_url("/atom.xml")),
+    title = escape(&config.feed.title),
+    subtitle = escape(&config.feed.description),
+    logo = config
+      .feed
+      .image
+      .as_ref()
+      .map_or_else(String::new, |image| format!(
+        "<logo>{}</logo>",
+        escape(image)
+      )),
+    updated = updated.to_rfc3339(),
+    entries = entries.join(""),
+  )
+}
+
+/// A JSON Feed (version 1.1).
+#[derive(Debug, Serialize)]
+pub struct JsonFeed {
+  version: &'static str,
+  title: String,
+  description: String,
+  home_page_url: String,
+  feed_url: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  icon: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  language: Option<String>,
+  items: Vec<JsonFeedItem>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonFeedItem {
+  id: String,
+  url: String,
+  title: String,
+  content_html: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  summary: Option<String>,
+  date_published: String,
+  date_modified: String,
+  authors: Vec<JsonFeedAuthor>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonFeedAuthor {
+  name: String,
+}
+
+impl JsonFeed {
+  pub fn new(config: &Config, entries: Vec<FeedEntry>) -> Self {
+    let items = entries
+      .into_iter()
+      .map(|entry| JsonFeedItem {
+        id: entry.id,
+        url: entry.url,
+        title: entry.title,
+        content_html: entry.content_html,
+        summary: entry.summary,
+        date_published: entry.published.to_rfc3339(),
+        date_modified: entry.updated.to_rfc3339(),
+        authors: entry
+          .authors
+          .into_iter()
+          .map(|author| JsonFeedAuthor {
+            name: split_author(&author).0.to_owned(),
+          })
+          .collect(),
+        tags: entry.tags,
+      })
+      .collect();
+
+    Self {
+      version: "https://jsonfeed.org/version/1.1",
+      title: config.feed.title.clone(),
+      description: config.feed.description.clone(),
+      home_page_url: config.site_url("/"),
+      feed_url: config.site_url("/feed.json"),
+      icon: config.feed.image.clone(),
+      language: config.feed.language.clone(),
+      items,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_split_author() {
+    assert_eq!(
+      split_author("editor@example.com (Jane Doe)"),
+      ("Jane Doe", Some("editor@example.com"))
+    );
+    assert_eq!(
+      split_author("editor@example.com"),
+      ("editor@example.com", Some("editor@example.com"))
+    );
+    assert_eq!(split_author("Jane Doe"), ("Jane Doe", None));
+    assert_eq!(split_author("@jane"), ("@jane", None));
+    assert_eq!(split_author("jane@localhost"), ("jane@localhost", None));
+  }
+}
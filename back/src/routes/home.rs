@@ -1,7 +1,11 @@
-use crate::{cache::Cache, html_wrapper::html_wrap};
+use crate::{
+  cache::Cache,
+  html_wrapper::{escape, html_wrap},
+  routes::week,
+};
 use rocket::{get, response::content::RawHtml, State};
 use std::cmp::Reverse;
-use twin::news::NewsState;
+use twin::news::{NewsKey, NewsState, NewsStore};
 
 /// Maximum number of updates to display on the home page.
 const MAX_UPDATES_DISPLAYED: usize = 5;
@@ -12,7 +16,7 @@ pub fn home(cache: &State<Cache>, state: &State<NewsState>) -> RawHtml<String> {
 }
 
 fn render(state: &NewsState) -> String {
-  let store = state.news_store().read().expect("news store");
+  let store = state.news_store();
   let mut keys: Vec<_> = store.keys().collect();
   let keys_len = keys.len();
 
@@ -20,21 +24,8 @@ fn render(state: &NewsState) -> String {
 
   let news_list: Vec<_> = keys
     .into_iter()
-    .enumerate()
     .take(MAX_UPDATES_DISPLAYED)
-    .map(|(k, key)| {
-      let href = format!("/{}/{}/{:02}", key.year, key.month, key.day);
-      let k = keys_len - k;
-
-      format!(
-        include_str!("./home_listing.html"),
-        href = href,
-        key_year = key.year,
-        key_month = key.month,
-        key_day = key.day,
-        k = k,
-      )
-    })
+    .map(|key| listing_item(&store, key))
     .collect();
 
   let html = format!(
@@ -45,3 +36,42 @@ fn render(state: &NewsState) -> String {
 
   html_wrap("", html)
 }
+
+/// Render a single entry of a listing of weekly news.
+///
+/// The title and summary are taken from the metadata of the news, if any.
+pub fn listing_item(store: &NewsStore, key: &NewsKey) -> String {
+  let href = week::canonical_path(key);
+  let metadata = store.get(key).map(|news| &news.metadata);
+  let title = escape(&news_title(store, key));
+  let summary = metadata
+    .and_then(|metadata| metadata.summary.as_deref())
+    .map_or_else(String::new, |summary| {
+      format!(
+        r#"<p class="is-size-6 has-text-grey has-text-right">{}</p>"#,
+        escape(summary)
+      )
+    });
+
+  format!(
+    include_str!("./home_listing.html"),
+    href = href,
+    key_year = key.year,
+    key_month = key.month,
+    key_day = key.day,
+    title = title,
+    summary = summary,
+  )
+}
+
+/// Title of a weekly news, as displayed in listings.
+///
+/// It is taken from the metadata of the news, if any, or derived from its issue number.
+pub fn news_title(store: &NewsStore, key: &NewsKey) -> String {
+  let title = store.get(key).and_then(|news| news.metadata.title.clone());
+
+  title.unwrap_or_else(|| match store.number(key) {
+    Some(number) => format!("This Week in Neovim #{}", number),
+    None => "This Week in Neovim".to_owned(),
+  })
+}
@@ -0,0 +1,147 @@
+use crate::{
+  cache::Cache,
+  html_wrapper::{escape, html_wrap},
+  routes::{home, week},
+};
+use rocket::{
+  get,
+  response::{content::RawHtml, status::NotFound},
+  State,
+};
+use std::collections::BTreeSet;
+use twin::{
+  news::{NewsKey, NewsState},
+  plugins::{Mention, Repository},
+};
+
+/// Every repository mentioned in weekly news.
+#[get("/plugins")]
+pub fn plugins(cache: &State<Cache>, state: &State<NewsState>) -> RawHtml<String> {
+  RawHtml(cache.cache("/plugins", || render_plugins(state)))
+}
+
+/// History of the mentions of a single repository.
+#[get("/plugins/<owner>/<name>")]
+pub fn plugin(
+  owner: &str,
+  name: &str,
+  cache: &State<Cache>,
+  state: &State<NewsState>,
+) -> Result<RawHtml<String>, NotFound<String>> {
+  let repository = Repository::new(owner, name);
+
+  cache
+    .cache_if_any(&cache_key(&repository), || {
+      render_plugin(state, &repository)
+    })
+    .map(RawHtml)
+    .ok_or_else(|| NotFound(format!("{repository} was never mentioned")))
+}
+
+/// Cache key of the page of a repository.
+fn cache_key(repository: &Repository) -> String {
+  format!("/plugins/{}/{}", repository.owner, repository.name)
+}
+
+fn render_plugins(state: &NewsState) -> String {
+  let store = state.news_store();
+  let index = store.plugin_index();
+
+  let repositories: Vec<_> = index
+    .repositories()
+    .map(|(repository, mentions)| {
+      let date = |mention: Option<&Mention>| {
+        mention.map_or_else(String::new, |mention| format_key(&mention.key))
+      };
+
+      format!(
+        r#"<tr><td><a href="{href}">{repository}</a></td><td>{mentions_len}</td><td>{first}</td><td>{last}</td></tr>"#,
+        href = escape(&cache_key(repository)),
+        repository = escape(&repository.to_string()),
+        mentions_len = mentions.len(),
+        first = date(mentions.first()),
+        last = date(mentions.last()),
+      )
+    })
+    .collect();
+
+  let html = format!(
+    include_str!("plugins.html"),
+    repositories_len = repositories.len(),
+    repositories = repositories.join("")
+  );
+
+  html_wrap("Plugins", html)
+}
+
+fn render_plugin(state: &NewsState, repository: &Repository) -> Option<String> {
+  let store = state.news_store();
+  let mentions = store.plugin_index().mentions(repository);
+
+  if mentions.is_empty() {
+    return None;
+  }
+
+  // a news can mention a repository several times
+  let news_len = mentions
+    .iter()
+    .map(|mention| mention.key)
+    .collect::<BTreeSet<_>>()
+    .len();
+
+  // most recent mentions first
+  let mentions_html: Vec<_> = mentions
+    .iter()
+    .rev()
+    .map(|mention| {
+      let key = mention.key;
+      let mut href = week::canonical_path(&key);
+
+      if let Some(ref anchor) = mention.anchor {
+        href.push('#');
+        href.push_str(anchor);
+      }
+
+      let heading = if mention.heading.is_empty() {
+        String::new()
+      } else {
+        format!(" — {}", escape(&mention.heading))
+      };
+
+      // how the news refers to the repository, e.g. a plugin name or a pull request
+      let text = if mention.text.is_empty() {
+        String::new()
+      } else {
+        format!(
+          r#"<p class="has-text-grey">Linked as “{}”</p>"#,
+          escape(&mention.text)
+        )
+      };
+
+      format!(
+        include_str!("plugin_mention.html"),
+        href = escape(&href),
+        title = escape(&home::news_title(&store, &key)),
+        heading = heading,
+        text = text,
+        key_year = key.year,
+        key_month = key.month,
+        key_day = key.day,
+      )
+    })
+    .collect();
+
+  let html = format!(
+    include_str!("plugin.html"),
+    repository = escape(&repository.to_string()),
+    url = escape(&repository.url()),
+    news_len = news_len,
+    mentions = mentions_html.join("")
+  );
+
+  Some(html_wrap(repository.to_string(), html))
+}
+
+fn format_key(key: &NewsKey) -> String {
+  format!("{} {} {:02}", key.year, key.month, key.day)
+}
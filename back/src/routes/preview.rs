@@ -0,0 +1,165 @@
+//! Previews of the weekly news that are not published yet, for editors.
+//!
+//! Drafts and scheduled news are hidden from every other page. They can be read under `/preview` by editors knowing
+//! the secret set in the configuration (see [`crate::routes::secret`]); without a secret, previews are disabled.
+//! Previews are never cached.
+
+use crate::{
+  html_wrapper::{escape, html_wrap},
+  routes::{
+    secret::{self, SecretForm},
+    week::{self, MonthParam},
+  },
+};
+use rocket::{
+  form::Form,
+  get,
+  http::{ContentType, CookieJar, Status},
+  post,
+  request::{FromRequest, Outcome, Request},
+  response::{content::RawHtml, Redirect},
+  State,
+};
+use std::path::PathBuf;
+use twin::news::{NewsKey, NewsState};
+
+/// Secret giving access to previews, if previews are enabled.
+pub struct PreviewSecret(pub Option<String>);
+
+/// Guard of the previews: the request must carry the preview secret.
+pub struct PreviewAccess;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for PreviewAccess {
+  type Error = ();
+
+  async fn from_request(request: &'r Request<'_>) -> Outcome<Self, ()> {
+    let expected = request
+      .rocket()
+      .state::<PreviewSecret>()
+      .and_then(|preview| preview.0.as_deref());
+
+    match secret::check_request(request, expected) {
+      Some(()) => Outcome::Success(PreviewAccess),
+      None => Outcome::Forward(Status::NotFound),
+    }
+  }
+}
+
+/// Form asking for the preview secret, when it is missing or wrong.
+#[get("/preview", rank = 2)]
+pub fn unlock_form(preview: &State<PreviewSecret>) -> Option<RawHtml<String>> {
+  secret::form("/preview", "Preview", preview.0.as_deref())
+}
+
+/// Remember the preview secret submitted by an editor.
+#[post("/preview", data = "<form>")]
+pub fn unlock(
+  form: Form<SecretForm>,
+  preview: &State<PreviewSecret>,
+  cookies: &CookieJar<'_>,
+) -> Redirect {
+  secret::unlock("/preview", preview.0.as_deref(), form, cookies)
+}
+
+/// List of the news that are not published yet.
+#[get("/preview")]
+pub fn list(_access: PreviewAccess, state: &State<NewsState>) -> RawHtml<String> {
+  let store = state.news_store();
+  let news_list: String = store
+    .unpublished_keys()
+    .rev()
+    .filter_map(|key| {
+      let news = store.get_unpublished(key)?;
+      let status = match news.metadata.publish_at {
+        _ if news.metadata.draft => "draft".to_owned(),
+        Some(publish_at) => format!("scheduled for {}", publish_at.to_rfc3339()),
+        None => "pending".to_owned(),
+      };
+      let title = news
+        .metadata
+        .title
+        .clone()
+        .unwrap_or_else(|| format!("{} {} {}", key.day, key.month, key.year));
+
+      Some(format!(
+        r#"<li><a href="/preview/{year}/{month}/{day:02}">{title}</a> <span class="tag">{status}</span></li>"#,
+        year = key.year,
+        month = key.month,
+        day = key.day,
+        title = escape(&title),
+        status = escape(&status),
+      ))
+    })
+    .collect();
+
+  let html = format!(
+    r#"<div class="section container"><h1 class="title">Unpublished news</h1><ul>{}</ul></div>"#,
+    news_list
+  );
+
+  RawHtml(html_wrap("Preview", html))
+}
+
+/// Preview of a weekly news, published or not.
+#[get("/preview/<year>/<month>/<day>")]
+pub fn by_key(
+  year: u16,
+  month: MonthParam,
+  day: u8,
+  _access: PreviewAccess,
+  state: &State<NewsState>,
+) -> Option<RawHtml<String>> {
+  let MonthParam(month) = month;
+  let key = NewsKey { year, month, day };
+  let store = state.news_store();
+  let (news, notice) = match store.get_unpublished(&key) {
+    Some(news) => {
+      let status = match news.metadata.publish_at {
+        _ if news.metadata.draft => "This news is a draft.".to_owned(),
+        Some(publish_at) => format!("This news is scheduled for {}.", publish_at.to_rfc3339()),
+        None => "This news is not published yet.".to_owned(),
+      };
+      let notice = format!(
+        r#"<div class="notification is-warning">{}</div>"#,
+        escape(&status)
+      );
+
+      (news, notice)
+    }
+
+    None => (store.get(&key)?, String::new()),
+  };
+
+  // assets of unpublished news are only served under the preview URL
+  let preview_news = store
+    .reparse_with_assets_url(key, format!("/preview{}", key.assets_url()))
+    .unwrap_or_else(|err| {
+      log::warn!("cannot parse news {:?} for preview: {}", key, err);
+      None
+    });
+  let html = week::render_page(key, preview_news.as_ref().unwrap_or(news), &notice, None);
+
+  Some(RawHtml(html))
+}
+
+/// Asset of a weekly news, published or not.
+#[get("/preview/<year>/<month>/<day>/assets/<path..>")]
+pub fn asset(
+  year: u16,
+  month: MonthParam,
+  day: u8,
+  path: PathBuf,
+  _access: PreviewAccess,
+  state: &State<NewsState>,
+) -> Option<(ContentType, Vec<u8>)> {
+  let MonthParam(month) = month;
+  let key = NewsKey { year, month, day };
+  let asset = week::asset_name(&path);
+  let store = state.news_store();
+  let content = store
+    .read_unpublished_asset(&key, &asset)
+    .or_else(|| store.read_asset(&key, &asset))?;
+
+  Some((week::asset_content_type(&path), content))
+}
@@ -1,57 +1,281 @@
+use chrono::{DateTime, Utc};
 use rocket::{get, response::content::RawXml, State};
-use std::cmp::Reverse;
-use twin::news::{News, NewsKey, NewsState, NewsStore};
+use twin::{
+  issue::slugify,
+  news::{NewsState, NewsStore},
+};
 
-use crate::cache::Cache;
+use crate::{
+  cache::Cache,
+  config::Config,
+  routes::feeds::{self, FeedEntry},
+};
 
 #[get("/rss")]
-pub fn rss(cache: &State<Cache>, state: &State<NewsState>) -> RawXml<String> {
+pub fn rss(
+  cache: &State<Cache>,
+  state: &State<NewsState>,
+  config: &State<Config>,
+) -> RawXml<String> {
   RawXml(cache.cache("/rss", || {
-    let news_store = state.news_store().read().expect("news store");
-    let feed = rss_feed(&news_store);
+    let news_store = state.news_store();
+    let feed = rss_feed(config, &news_store);
     feed.to_string()
   }))
 }
 
-pub fn news_to_rss(key: &NewsKey, content: Option<&News>) -> ::rss::Item {
-  ::rss::ItemBuilder::default()
-    .author(Some(
-      "Dimitri 'phaazon' Sabadie <dimitri.sabadie@gmail.com>".to_owned(),
-    ))
-    .pub_date(Some(format_date(key)))
-    .link(Some(format!(
-      "https://this-week-in-neovim.org/{}/{}/{}",
-      key.year, key.month, key.day
-    )))
-    .title(Some(format_date(key)))
-    .description(match content {
-      Some(item) => Some(item.html.to_owned()),
-      None => None,
-    })
-    .build()
+/// Feed restricted to the sections of the weekly news about a topic, e.g. `/rss/core` or `/rss/plugins`.
+///
+/// Only topics naming a section get a feed; see [`twin::issue::Issue::has_section_named`].
+#[get("/rss/<section>")]
+pub fn section(
+  section: &str,
+  cache: &State<Cache>,
+  state: &State<NewsState>,
+  config: &State<Config>,
+) -> Option<RawXml<String>> {
+  let topic = slugify(section);
+
+  filtered_rss(cache, &format!("/rss/{}", topic), config, &topic, || {
+    let store = state.news_store();
+    let named = store
+      .keys()
+      .filter_map(|key| store.get(key))
+      .any(|news| news.issue.has_section_named(&topic));
+
+    if named {
+      feeds::section_entries(config, &store, &topic)
+    } else {
+      Vec::new()
+    }
+  })
+}
+
+/// Feed restricted to the news and parts of news tagged with a tag.
+#[get("/rss/tag/<tag>")]
+pub fn tag(
+  tag: &str,
+  cache: &State<Cache>,
+  state: &State<NewsState>,
+  config: &State<Config>,
+) -> Option<RawXml<String>> {
+  // tags are matched ignoring case
+  let tag = tag.to_lowercase();
+
+  filtered_rss(cache, &format!("/rss/tag/{}", tag), config, &tag, || {
+    feeds::tag_entries(config, &state.news_store(), &tag)
+  })
+}
+
+/// Render a filtered feed, if it has any entry.
+///
+/// Feeds without entries are not found, and not cached, so that requesting arbitrary topics doesn’t fill the cache.
+fn filtered_rss(
+  cache: &Cache,
+  cache_key: &str,
+  config: &Config,
+  topic: &str,
+  entries: impl FnOnce() -> Vec<FeedEntry>,
+) -> Option<RawXml<String>> {
+  let feed = cache.cache_if_any(cache_key, || {
+    let entries = entries();
+
+    if entries.is_empty() {
+      None
+    } else {
+      Some(rss_channel(config, Some(topic), entries).to_string())
+    }
+  })?;
+
+  Some(RawXml(feed))
 }
 
-pub fn rss_feed(news_store: &NewsStore) -> ::rss::Channel {
-  let mut items: Vec<_> = news_store
-    .keys()
+pub fn entry_to_rss(entry: FeedEntry) -> ::rss::Item {
+  let categories: Vec<_> = entry
+    .tags
     .into_iter()
-    .map(|key| (key, news_to_rss(key, news_store.get(key))))
+    .map(|name| ::rss::Category { name, domain: None })
     .collect();
-  items.sort_by_key(|(key, _)| Reverse(*key));
 
-  let last_build_date = items.get(0).map(|(key, _)| format_date(key));
+  // the URL of a news never changes, so it is a permanent identifier; entries restricted to a topic are not links
+  let guid = ::rss::Guid {
+    permalink: entry.id == entry.url,
+    value: entry.id,
+  };
+
+  // RSS authors must be email addresses; the first one is the author, and everybody else a Dublin Core creator
+  let mut authors = entry.authors;
+  let author = authors
+    .iter()
+    .position(|author| feeds::split_author(author).1.is_some())
+    .map(|index| authors.remove(index));
+  let dublin_core = if authors.is_empty() {
+    None
+  } else {
+    Some(
+      ::rss::extension::dublincore::DublinCoreExtensionBuilder::default()
+        .creators(authors)
+        .build(),
+    )
+  };
+
+  ::rss::ItemBuilder::default()
+    .author(author)
+    .dublin_core_ext(dublin_core)
+    .pub_date(Some(rfc822(&entry.published)))
+    .link(Some(entry.url))
+    .guid(Some(guid))
+    .title(Some(entry.title))
+    .categories(categories)
+    .description(Some(entry.content_html))
+    .build()
+}
+
+pub fn rss_feed(config: &Config, news_store: &NewsStore) -> ::rss::Channel {
+  rss_channel(config, None, feeds::feed_entries(config, news_store))
+}
+
+/// Build a channel from feed entries, most recent first, optionally restricted to a topic.
+fn rss_channel(config: &Config, topic: Option<&str>, entries: Vec<FeedEntry>) -> ::rss::Channel {
+  let feed = &config.feed;
+  let title = topic.map_or_else(
+    || feed.title.clone(),
+    |topic| format!("{} — {}", feed.title, topic),
+  );
+
+  let last_build_date = entries
+    .iter()
+    .map(|entry| entry.updated)
+    .max()
+    .map(|date| rfc822(&date));
+  let items: Vec<_> = entries.into_iter().map(entry_to_rss).collect();
 
-  let items: Vec<_> = items.into_iter().map(|(_, news)| news).collect();
+  let image = feed.image.as_ref().map(|url| {
+    ::rss::ImageBuilder::default()
+      .url(url.clone())
+      .title(title.clone())
+      .link(config.site_url("/"))
+      .build()
+  });
 
   ::rss::ChannelBuilder::default()
-    .title("This Week In Neovim".to_owned())
-    .link("https://this-week-in-neovim.org".to_owned())
+    .title(title)
+    .link(config.site_url("/"))
+    .description(feed.description.clone())
+    .language(feed.language.clone())
+    .managing_editor(feed.managing_editor.clone())
+    .image(image)
     .items(items)
     .last_build_date(last_build_date)
     .build()
 }
 
-/// Create RFC 822 conforming date-time from a newskey.
-fn format_date(key: &NewsKey) -> String {
-  format!("{} {} {} {} GMT", key.day, key.month, key.year, "00:00:00")
+/// Format a date as required by RSS (RFC 822), with a zero-padded day, e.g. `Mon, 06 Jun 2022 00:00:00 +0000`.
+fn rfc822(date: &DateTime<Utc>) -> String {
+  date.format("%a, %d %b %Y %H:%M:%S %z").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rocket::{http::Status, local::blocking::Client, routes};
+  use std::time::Duration;
+  use twin::news::MemorySource;
+
+  fn client() -> Client {
+    let config: Config =
+      toml::from_str("port = 8000\nnews_root = \"contents\"\nstatic_dir = \"static\"").unwrap();
+    let source = MemorySource::new().with_file(
+      "2022/Jun/06.md",
+      "# Neovim core\n\n### Faster {.perf}\n\nFaster.\n\n# Plugins\n\nNew plugins.",
+    );
+    let mut store = NewsStore::new("contents").with_source(source);
+    store.populate_from_root().unwrap();
+
+    let rocket = rocket::build()
+      .manage(Cache::new(Duration::from_secs(60)))
+      .manage(NewsState::from_store(store))
+      .manage(config)
+      .mount("/", routes![section, tag]);
+    Client::tracked(rocket).expect("valid rocket instance")
+  }
+
+  #[test]
+  fn test_topics() {
+    let client = client();
+
+    let core = client.get("/rss/core").dispatch().into_string().unwrap();
+    assert!(core.contains(
+      r#"<guid isPermaLink="false">https://this-week-in-neovim.org/2022/Jun/06#core</guid>"#
+    ));
+    assert!(core.contains("<link>https://this-week-in-neovim.org/2022/Jun/06#neovim-core</link>"));
+    assert_eq!(client.get("/rss/Core").dispatch().into_string(), Some(core));
+    assert_eq!(
+      client.get("/rss/neovim%20core").dispatch().status(),
+      Status::Ok
+    );
+
+    assert_eq!(
+      client.get("/rss/core-neovim").dispatch().status(),
+      Status::NotFound
+    );
+    assert_eq!(
+      client.get("/rss/faster").dispatch().status(),
+      Status::NotFound
+    );
+
+    let perf = client
+      .get("/rss/tag/PERF")
+      .dispatch()
+      .into_string()
+      .unwrap();
+    assert!(perf.contains(
+      r#"<guid isPermaLink="false">https://this-week-in-neovim.org/2022/Jun/06#perf</guid>"#
+    ));
+    assert_eq!(
+      client.get("/rss/tag/core").dispatch().status(),
+      Status::NotFound
+    );
+  }
+
+  fn entry(authors: &[&str]) -> FeedEntry {
+    FeedEntry {
+      id: "https://example.com/2022/Jun/06".to_owned(),
+      url: "https://example.com/2022/Jun/06".to_owned(),
+      title: "TWiN".to_owned(),
+      summary: None,
+      authors: authors.iter().map(|author| author.to_string()).collect(),
+      tags: Vec::new(),
+      content_html: String::new(),
+      published: Utc::now(),
+      updated: Utc::now(),
+    }
+  }
+
+  #[test]
+  fn test_authors() {
+    let item = entry_to_rss(entry(&[
+      "Jane Doe",
+      "editor@example.com (John Doe)",
+      "bob@example.com",
+    ]));
+    assert_eq!(item.author(), Some("editor@example.com (John Doe)"));
+    assert_eq!(
+      item.dublin_core_ext().map(|dc| dc.creators()),
+      Some(&["Jane Doe".to_owned(), "bob@example.com".to_owned()][..])
+    );
+
+    let item = entry_to_rss(entry(&["Jane Doe"]));
+    assert_eq!(item.author(), None);
+    let channel = ::rss::ChannelBuilder::default()
+      .items(vec![item])
+      .build()
+      .to_string();
+    assert!(channel.contains(r#"xmlns:dc="http://purl.org/dc/elements/1.1/""#));
+    assert!(channel.contains("<dc:creator>Jane Doe</dc:creator>"));
+
+    let item = entry_to_rss(entry(&[]));
+    assert_eq!(item.author(), None);
+    assert_eq!(item.dublin_core_ext(), None);
+  }
 }
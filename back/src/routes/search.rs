@@ -0,0 +1,85 @@
+use crate::{
+  html_wrapper::{escape, html_wrap},
+  routes::{home, week},
+};
+use rocket::{get, response::content::RawHtml, State};
+use twin::{news::NewsState, search::Snippet};
+
+/// Maximum number of search results to display.
+const MAX_RESULTS: usize = 50;
+
+/// Full-text search across all weekly news.
+///
+/// Results depend on the query, so this page is never cached.
+#[get("/search?<q>")]
+pub fn search(q: Option<&str>, state: &State<NewsState>) -> RawHtml<String> {
+  let store = state.news_store();
+  let query = q.unwrap_or_default().trim();
+  let results = store.search_index().search(query, MAX_RESULTS);
+
+  let results_html: Vec<_> = results
+    .iter()
+    .map(|result| {
+      let key = result.key;
+      let mut href = week::canonical_path(&key);
+
+      if let Some(ref anchor) = result.anchor {
+        href.push('#');
+        href.push_str(anchor);
+      }
+
+      let heading = if result.heading.is_empty() {
+        String::new()
+      } else {
+        format!(" — {}", escape(&result.heading))
+      };
+
+      format!(
+        include_str!("search_result.html"),
+        href = escape(&href),
+        title = escape(&home::news_title(&store, &key)),
+        heading = heading,
+        key_year = key.year,
+        key_month = key.month,
+        key_day = key.day,
+        snippet = render_snippet(&result.snippet),
+      )
+    })
+    .collect();
+
+  let summary = if query.is_empty() {
+    String::new()
+  } else {
+    format!(
+      r#"<p class="block"><b>{}</b> result(s) for <b>{}</b>.</p>"#,
+      results.len(),
+      escape(query)
+    )
+  };
+
+  let html = format!(
+    include_str!("search.html"),
+    query = escape(query),
+    summary = summary,
+    results = results_html.join("")
+  );
+
+  RawHtml(html_wrap("Search", html))
+}
+
+/// Render a snippet as HTML, wrapping the matching words in `<mark>`.
+fn render_snippet(snippet: &Snippet) -> String {
+  let mut html = String::new();
+  let mut last = 0;
+
+  for highlight in &snippet.highlights {
+    html.push_str(&escape(&snippet.text[last..highlight.start]));
+    html.push_str("<mark>");
+    html.push_str(&escape(&snippet.text[highlight.clone()]));
+    html.push_str("</mark>");
+    last = highlight.end;
+  }
+
+  html.push_str(&escape(&snippet.text[last..]));
+  html
+}
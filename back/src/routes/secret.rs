@@ -0,0 +1,89 @@
+//! Secrets giving editors access to restricted pages, such as previews and diagnostics.
+//!
+//! Secrets never appear in URLs, as URLs end up in logs. A client sends a secret either in the `X-Twin-Secret` header,
+//! or in a cookie, set by submitting the form shown on restricted pages when the secret is missing or wrong.
+
+use crate::html_wrapper::{escape, html_wrap};
+use rocket::{
+  form::Form,
+  http::{Cookie, CookieJar, SameSite},
+  request::Request,
+  response::{content::RawHtml, Redirect},
+  FromForm,
+};
+
+/// Header holding a secret.
+const SECRET_HEADER: &str = "X-Twin-Secret";
+
+/// Cookie holding a secret; it is restricted to the path of the pages it gives access to.
+const SECRET_COOKIE: &str = "twin_secret";
+
+/// Form submitted to unlock restricted pages.
+#[derive(FromForm)]
+pub struct SecretForm {
+  secret: String,
+}
+
+/// Check the secret sent with a request against the configured one.
+///
+/// Without a configured secret, every check fails.
+pub fn check_request(request: &Request<'_>, expected: Option<&str>) -> Option<()> {
+  let secret = match request.headers().get_one(SECRET_HEADER) {
+    Some(secret) => secret.to_owned(),
+    None => request.cookies().get(SECRET_COOKIE)?.value().to_owned(),
+  };
+
+  check_secret(expected, &secret)
+}
+
+/// Check a secret against the configured one; without a configured secret, every check fails.
+pub fn check_secret(expected: Option<&str>, secret: &str) -> Option<()> {
+  let expected = expected.filter(|expected| !expected.is_empty())?;
+
+  // compare in constant time, so that the secret cannot be guessed by timing requests
+  let diff = expected
+    .bytes()
+    .zip(secret.bytes())
+    .fold(expected.len() ^ secret.len(), |diff, (a, b)| {
+      diff | usize::from(a ^ b)
+    });
+
+  if diff == 0 {
+    Some(())
+  } else {
+    None
+  }
+}
+
+/// Form asking for the secret of the restricted pages under `path`, if they are enabled.
+pub fn form(path: &str, title: &str, expected: Option<&str>) -> Option<RawHtml<String>> {
+  expected.filter(|expected| !expected.is_empty())?;
+
+  let html = format!(
+    r#"<div class="section container"><h1 class="title">{title}</h1><form method="post" action="{path}"><div class="field"><label class="label" for="secret">Secret</label><div class="control"><input class="input" type="password" id="secret" name="secret" autocomplete="current-password"></div></div><div class="control"><button class="button is-link" type="submit">Unlock</button></div></form></div>"#,
+    title = escape(title),
+    path = escape(path),
+  );
+
+  Some(RawHtml(html_wrap(title, html)))
+}
+
+/// Remember a submitted secret for the restricted pages under `path`, and go back to them.
+///
+/// Wrong secrets are not remembered; the form is then shown again.
+pub fn unlock(
+  path: &'static str,
+  expected: Option<&str>,
+  form: Form<SecretForm>,
+  cookies: &CookieJar<'_>,
+) -> Redirect {
+  if check_secret(expected, &form.secret).is_some() {
+    let cookie = Cookie::build((SECRET_COOKIE, form.into_inner().secret))
+      .path(path)
+      .http_only(true)
+      .same_site(SameSite::Strict);
+    cookies.add(cookie);
+  }
+
+  Redirect::to(path)
+}
@@ -0,0 +1,145 @@
+//! Sitemap and `robots.txt`, for search engines.
+
+use crate::{
+  cache::Cache,
+  config::Config,
+  html_wrapper::escape,
+  routes::{feeds, week},
+};
+use rocket::{
+  get,
+  response::content::{RawText, RawXml},
+  State,
+};
+use twin::news::NewsState;
+
+#[get("/sitemap.xml")]
+pub fn sitemap(
+  cache: &State<Cache>,
+  state: &State<NewsState>,
+  config: &State<Config>,
+) -> RawXml<String> {
+  RawXml(cache.cache("/sitemap.xml", || render_sitemap(config, state)))
+}
+
+#[get("/robots.txt")]
+pub fn robots(config: &State<Config>) -> RawText<String> {
+  let robots = config.robots_txt.clone().unwrap_or_else(|| {
+    format!(
+      "User-agent: *\nDisallow: /preview\nDisallow: /diagnostics\n\nSitemap: {}\n",
+      config.site_url("/sitemap.xml")
+    )
+  });
+
+  RawText(robots)
+}
+
+fn render_sitemap(config: &Config, state: &NewsState) -> String {
+  let store = state.news_store();
+  let url = |path: &str, lastmod: Option<String>| {
+    let lastmod = lastmod.map_or_else(String::new, |lastmod| {
+      format!("<lastmod>{}</lastmod>", lastmod)
+    });
+
+    format!(
+      "<url><loc>{}</loc>{}</url>",
+      escape(&config.site_url(path)),
+      lastmod
+    )
+  };
+
+  // most recent news first
+  let news: Vec<_> = store
+    .keys()
+    .rev()
+    .filter_map(|key| {
+      let updated = feeds::updated_date(key, store.get(key)?);
+      Some((*key, updated.format("%Y-%m-%d").to_string()))
+    })
+    .collect();
+
+  // listings change every time a news does
+  let last_update = news.iter().map(|(_, lastmod)| lastmod.clone()).max();
+
+  let mut urls = vec![
+    url("/", last_update.clone()),
+    url("/all", last_update),
+    url("/plugins", None),
+  ];
+  urls.extend(
+    news
+      .into_iter()
+      .map(|(key, lastmod)| url(&week::canonical_path(&key), Some(lastmod))),
+  );
+
+  format!(
+    r#"<?xml version="1.0" encoding="UTF-8"?><urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">{}</urlset>"#,
+    urls.join("")
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rocket::{local::blocking::Client, routes};
+  use twin::news::{MemorySource, NewsStore};
+
+  fn config(extra: &str) -> Config {
+    let config = format!(
+      "port = 8000\nnews_root = \"contents\"\nstatic_dir = \"static\"\nbase_url = \"https://example.com/\"\n{}",
+      extra
+    );
+    toml::from_str(&config).unwrap()
+  }
+
+  #[test]
+  fn test_sitemap() {
+    let source = MemorySource::new()
+      .with_file("2022/Jun/06.md", "# First")
+      .with_file("2022/Jun/13.md", "# Second");
+    let mut store = NewsStore::new("contents").with_source(source);
+    store.populate_from_root().unwrap();
+
+    let sitemap = render_sitemap(&config(""), &NewsState::from_store(store));
+    assert_eq!(
+      sitemap,
+      concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#,
+        "<url><loc>https://example.com/</loc><lastmod>2022-06-13</lastmod></url>",
+        "<url><loc>https://example.com/all</loc><lastmod>2022-06-13</lastmod></url>",
+        "<url><loc>https://example.com/plugins</loc></url>",
+        "<url><loc>https://example.com/2022/Jun/13</loc><lastmod>2022-06-13</lastmod></url>",
+        "<url><loc>https://example.com/2022/Jun/06</loc><lastmod>2022-06-06</lastmod></url>",
+        "</urlset>"
+      )
+    );
+
+    let sitemap = render_sitemap(
+      &config(""),
+      &NewsState::from_store(NewsStore::new("contents")),
+    );
+    assert!(sitemap.contains("<url><loc>https://example.com/</loc></url>"));
+    assert!(!sitemap.contains("lastmod"));
+  }
+
+  #[test]
+  fn test_robots() {
+    let robots = |config| {
+      let rocket = rocket::build().manage(config).mount("/", routes![robots]);
+      let client = Client::tracked(rocket).expect("valid rocket instance");
+      let response = client.get("/robots.txt").dispatch();
+      response.into_string()
+    };
+
+    assert_eq!(
+      robots(config("")).as_deref(),
+      Some(
+        "User-agent: *\nDisallow: /preview\nDisallow: /diagnostics\n\nSitemap: https://example.com/sitemap.xml\n"
+      )
+    );
+    assert_eq!(
+      robots(config("robots_txt = \"User-agent: *\\nDisallow: /\\n\"")).as_deref(),
+      Some("User-agent: *\nDisallow: /\n")
+    );
+  }
+}
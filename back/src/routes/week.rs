@@ -1,14 +1,26 @@
-use crate::{cache::Cache, html_wrapper::html_wrap};
+use crate::{
+  cache::Cache,
+  config::Config,
+  html_wrapper::{escape, html_wrap_with_canonical},
+  routes::feeds,
+};
 use rocket::{
   get,
+  http::{uri::Origin, ContentType},
   request::FromParam,
-  response::{content::RawHtml, status::NotFound},
-  State,
+  response::{content::RawHtml, status::NotFound, Redirect},
+  Responder, State,
+};
+use std::{
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+use twin::{
+  issue::TocEntry,
+  news::{Month, News, NewsKey, NewsState},
 };
-use std::str::FromStr;
-use twin::news::{Month, NewsKey, NewsState};
 
-pub struct MonthParam(Month);
+pub struct MonthParam(pub Month);
 
 impl<'a> FromParam<'a> for MonthParam {
   type Error = <Month as FromStr>::Err;
@@ -22,75 +34,275 @@ impl<'a> FromParam<'a> for MonthParam {
 pub fn latest(
   cache: &State<Cache>,
   state: &State<NewsState>,
+  config: &State<Config>,
 ) -> Result<RawHtml<String>, NotFound<String>> {
-  let news_store = state.news_store().read().expect("news store");
+  let news_store = state.news_store();
   let key = news_store
     .keys()
     .max()
     .ok_or_else(|| NotFound("no latest news available".to_owned()))?;
 
-  render(*key, cache, state).ok_or_else(|| NotFound("no latest news available".to_owned()))
+  render(*key, cache, state, config).ok_or_else(|| NotFound("no latest news available".to_owned()))
+}
+
+/// Stable link to a weekly news, by its issue number.
+///
+/// The redirection is temporary, as a news can take over a number via its metadata.
+#[get("/issue/<number>")]
+pub fn by_number(number: u32, state: &State<NewsState>) -> Result<Redirect, NotFound<String>> {
+  let key = state
+    .news_store()
+    .key_from_number(number)
+    .ok_or_else(|| NotFound(format!("issue #{number} doesn’t exist")))?;
+
+  Ok(Redirect::temporary(canonical_path(&key)))
+}
+
+/// Page of a weekly news, or a redirection to its canonical URL.
+#[derive(Responder)]
+pub enum WeekPage {
+  Page(RawHtml<String>),
+
+  // boxed, as redirections are way bigger than pages
+  Redirect(Box<Redirect>),
 }
 
+/// Page of a weekly news.
+///
+/// A news can be reached with equivalent dates, such as `/2022/Jun/6` and `/2022/Jun/06`; only the latter, with a
+/// zero-padded day, is canonical, and the others permanently redirect to it if the news exists.
 #[get("/<year>/<month>/<day>")]
 pub fn by_key(
   year: u16,
   month: MonthParam,
   day: u8,
+  uri: &Origin<'_>,
   cache: &State<Cache>,
   state: &State<NewsState>,
-) -> Result<RawHtml<String>, NotFound<String>> {
+  config: &State<Config>,
+) -> Result<WeekPage, NotFound<String>> {
   let MonthParam(month) = month;
   let key = NewsKey { year, month, day };
+  let not_found = || NotFound(format!("news {year}-{month}-{day} doesn’t exist"));
+
+  // redirections are permanent, so only existing news are redirected, rather than sending clients to a missing page
+  if state.news_store().get(&key).is_none() {
+    return Err(not_found());
+  }
+
+  let canonical = canonical_path(&key);
+  if uri.path().as_str() != canonical {
+    return Ok(WeekPage::Redirect(Box::new(Redirect::permanent(canonical))));
+  }
+
+  render(key, cache, state, config)
+    .map(WeekPage::Page)
+    .ok_or_else(not_found)
+}
+
+/// Canonical path of the page of a weekly news.
+pub fn canonical_path(key: &NewsKey) -> String {
+  format!("/{}/{}/{:02}", key.year, key.month, key.day)
+}
+
+/// Asset of a weekly news, such as a screenshot, stored next to its Markdown files.
+#[get("/<year>/<month>/<day>/assets/<path..>", rank = 2)]
+pub fn asset(
+  year: u16,
+  month: MonthParam,
+  day: u8,
+  path: PathBuf,
+  state: &State<NewsState>,
+) -> Option<(ContentType, Vec<u8>)> {
+  let MonthParam(month) = month;
+  let key = NewsKey { year, month, day };
+  let content = state.news_store().read_asset(&key, &asset_name(&path))?;
+
+  Some((asset_content_type(&path), content))
+}
+
+/// Content type of an asset, guessed from its extension.
+pub fn asset_content_type(path: &Path) -> ContentType {
+  path
+    .extension()
+    .and_then(|ext| ContentType::from_extension(&ext.to_string_lossy()))
+    .unwrap_or(ContentType::Binary)
+}
+
+/// Name of an asset, relative to the directory of its news, from the path found in its URL.
+pub fn asset_name(path: &Path) -> String {
+  let asset: Vec<_> = path
+    .components()
+    .map(|component| component.as_os_str().to_string_lossy())
+    .collect();
+
+  asset.join("/")
+}
 
-  render(key, cache, state)
-    .ok_or_else(|| NotFound(format!("news {year}-{month}-{day} doesn’t exist")))
+/// Cache key of the rendered page of a weekly news.
+pub fn cache_key(key: &NewsKey) -> String {
+  format!("/{}/{}/{}", key.year, key.month, key.day)
 }
 
-fn render(key: NewsKey, cache: &Cache, state: &NewsState) -> Option<RawHtml<String>> {
+fn render(
+  key: NewsKey,
+  cache: &Cache,
+  state: &NewsState,
+  config: &Config,
+) -> Option<RawHtml<String>> {
   cache
-    .cache_if_any(&format!("/{}/{}/{}", key.year, key.month, key.day), || {
-      let store = state.news_store().read().expect("news store");
+    .cache_if_any(&cache_key(&key), || {
+      let store = state.news_store();
       let news = store.get(&key)?;
+      let canonical = feeds::news_url(config, &key);
 
-      // if we have prev and/or next key, we need to generate the html for them
-      let prev_date = if let Some(prev) = news.prev {
-        format!(
-          include_str!("prev_date.html"),
-          day = prev.day,
-          month = prev.month,
-          year = prev.year
-        )
-      } else {
-        "".to_owned()
-      };
+      Some(render_page(key, news, "", Some(&canonical)))
+    })
+    .map(RawHtml)
+}
+
+/// Render the page of a weekly news, with an optional notice (HTML) shown above its contents.
+///
+/// `canonical` is the canonical URL of the page, if any.
+pub fn render_page(key: NewsKey, news: &News, notice: &str, canonical: Option<&str>) -> String {
+  // if we have prev and/or next key, we need to generate the html for them
+  let prev_date = if let Some(prev) = news.prev {
+    format!(
+      include_str!("prev_date.html"),
+      day = prev.day,
+      month = prev.month,
+      year = prev.year
+    )
+  } else {
+    "".to_owned()
+  };
+
+  let next_date = if let Some(next) = news.next {
+    format!(
+      include_str!("next_date.html"),
+      day = next.day,
+      month = next.month,
+      year = next.year
+    )
+  } else {
+    "".to_owned()
+  };
+
+  let last_updated = news.last_updated.map_or_else(String::new, |date| {
+    format!(
+      r#"<p class="is-size-7 has-text-grey">last updated on {}</p>"#,
+      date.format("%Y-%m-%d")
+    )
+  });
+
+  let title = news
+    .metadata
+    .title
+    .clone()
+    .unwrap_or_else(|| format!("{} {} {}", key.day, key.month, key.year));
+
+  html_wrap_with_canonical(
+    title,
+    canonical,
+    format!(
+      include_str!("week.html"),
+      notice = notice,
+      prev_date = prev_date,
+      next_date = next_date,
+      toc = render_toc(&news.toc),
+      day = key.day,
+      month = key.month,
+      year = key.year,
+      last_updated = last_updated,
+      contents = news.html
+    ),
+  )
+}
+
+/// Render the table of contents of a weekly news, if it has any heading.
+fn render_toc(toc: &[TocEntry]) -> String {
+  fn render_entries(entries: &[TocEntry]) -> String {
+    let items: String = entries
+      .iter()
+      .map(|entry| {
+        let children = if entry.children.is_empty() {
+          String::new()
+        } else {
+          render_entries(&entry.children)
+        };
 
-      let next_date = if let Some(next) = news.next {
         format!(
-          include_str!("next_date.html"),
-          day = next.day,
-          month = next.month,
-          year = next.year
+          r##"<li><a href="#{id}">{title}</a>{children}</li>"##,
+          id = escape(&entry.id),
+          title = escape(&entry.title),
+          children = children
         )
-      } else {
-        "".to_owned()
-      };
+      })
+      .collect();
 
-      let title = format!("{} {} {}", key.day, key.month, key.year);
-      let html = html_wrap(
-        title,
-        format!(
-          include_str!("week.html"),
-          prev_date = prev_date,
-          next_date = next_date,
-          day = key.day,
-          month = key.month,
-          year = key.year,
-          contents = news.html
-        ),
-      );
-
-      Some(html)
-    })
-    .map(RawHtml)
+    format!("<ul>{}</ul>", items)
+  }
+
+  if toc.is_empty() {
+    return String::new();
+  }
+
+  format!(include_str!("toc.html"), entries = render_entries(toc))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rocket::{http::Status, local::blocking::Client, routes};
+  use std::time::Duration;
+  use twin::news::{MemorySource, NewsStore};
+
+  #[test]
+  fn test_canonical_path() {
+    let key = NewsKey {
+      year: 2022,
+      month: Month::Jun,
+      day: 6,
+    };
+    assert_eq!(canonical_path(&key), "/2022/Jun/06");
+
+    let key = NewsKey {
+      year: 2023,
+      month: Month::Dec,
+      day: 25,
+    };
+    assert_eq!(canonical_path(&key), "/2023/Dec/25");
+  }
+
+  #[test]
+  fn test_redirect() {
+    let config: Config =
+      toml::from_str("port = 8000\nnews_root = \"contents\"\nstatic_dir = \"static\"").unwrap();
+    let mut store = NewsStore::new("contents")
+      .with_source(MemorySource::new().with_file("2022/Jun/06.md", "# Core"));
+    store.populate_from_root().unwrap();
+
+    let rocket = rocket::build()
+      .manage(Cache::new(Duration::from_secs(60)))
+      .manage(NewsState::from_store(store))
+      .manage(config)
+      .mount("/", routes![by_key]);
+    let client = Client::tracked(rocket).expect("valid rocket instance");
+
+    assert_eq!(client.get("/2022/Jun/06").dispatch().status(), Status::Ok);
+
+    let response = client.get("/2022/Jun/6").dispatch();
+    assert_eq!(response.status(), Status::PermanentRedirect);
+    assert_eq!(response.headers().get_one("Location"), Some("/2022/Jun/06"));
+
+    // missing news are not redirected
+    assert_eq!(
+      client.get("/2022/Jun/7").dispatch().status(),
+      Status::NotFound
+    );
+    assert_eq!(
+      client.get("/2022/Jun/07").dispatch().status(),
+      Status::NotFound
+    );
+  }
 }
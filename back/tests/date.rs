@@ -1,6 +1,7 @@
 use chrono::prelude::*;
 
 #[test]
+#[allow(deprecated)]
 fn test_building_dates() {
   let date = Utc.isoywd(2022, 26, Weekday::Mon);
 
@@ -0,0 +1,104 @@
+//! Assets of weekly news.
+//!
+//! A weekly news split into a directory can ship other files than Markdown, such as screenshots or GIFs, next to its
+//! content. Those files are the assets of the news. Relative links to them in the Markdown are rewritten to the URL the
+//! assets are served at.
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Characters escaped in a segment of the path of an asset: those escaped in URL paths, along with `%` and `/`.
+const SEGMENT: &AsciiSet = &CONTROLS
+  .add(b' ')
+  .add(b'"')
+  .add(b'#')
+  .add(b'<')
+  .add(b'>')
+  .add(b'?')
+  .add(b'`')
+  .add(b'{')
+  .add(b'}')
+  .add(b'%')
+  .add(b'/');
+
+/// Assets of a weekly news.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Assets {
+  /// URL under which the assets are served.
+  base_url: String,
+
+  /// Paths of the assets, relative to the directory of the news, with `/` separators.
+  paths: BTreeSet<String>,
+}
+
+impl Assets {
+  /// Create the set of assets served under `base_url`.
+  pub fn new(base_url: impl Into<String>, paths: impl IntoIterator<Item = String>) -> Self {
+    Self {
+      base_url: base_url.into(),
+      paths: paths.into_iter().collect(),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.paths.is_empty()
+  }
+
+  /// Check whether a path, relative to the directory of the news, is an asset.
+  pub fn contains(&self, path: &str) -> bool {
+    self.paths.contains(path)
+  }
+
+  /// Paths of the assets, relative to the directory of the news.
+  pub fn paths(&self) -> impl Iterator<Item = &str> {
+    self.paths.iter().map(String::as_str)
+  }
+
+  /// URL of an asset; each segment of its path is percent-encoded.
+  pub fn url(&self, path: &str) -> String {
+    let path: Vec<_> = path
+      .split('/')
+      .map(|segment| utf8_percent_encode(segment, SEGMENT).to_string())
+      .collect();
+    format!("{}/{}", self.base_url, path.join("/"))
+  }
+
+  /// Resolve a link found in a Markdown file, returning the URL of the asset it points to, if any.
+  ///
+  /// `file_dir` is the directory of the Markdown file, relative to the directory of the news. Only relative links are
+  /// resolved; links with a scheme, absolute paths and anchors are left alone.
+  pub fn resolve(&self, file_dir: &str, link: &str) -> Option<String> {
+    let path_len = link.find(['?', '#']).unwrap_or(link.len());
+    let (path, suffix) = link.split_at(path_len);
+    let has_scheme = path
+      .split_once(':')
+      .is_some_and(|(scheme, _)| !scheme.contains('/'));
+
+    if path.is_empty() || path.starts_with('/') || has_scheme {
+      return None;
+    }
+
+    let path = percent_decode_str(path).decode_utf8().ok()?;
+    let mut segments = Vec::new();
+
+    for segment in file_dir.split('/').chain(path.split('/')) {
+      match segment {
+        "" | "." => (),
+        ".." => {
+          // going out of the directory of the news
+          segments.pop()?;
+        }
+        segment => segments.push(segment),
+      }
+    }
+
+    let path = segments.join("/");
+
+    if self.contains(&path) {
+      Some(format!("{}{}", self.url(&path), suffix))
+    } else {
+      None
+    }
+  }
+}
@@ -0,0 +1,221 @@
+//! Shorthands turned into links while parsing weekly news.
+//!
+//! Contents mention Neovim pull requests, issues and plugins all the time. Instead of writing full links, contributors
+//! can write shorthands, such as `#12345`, `neovim/neovim#12345` or `gh:phaazon/hop.nvim`, that are turned into links
+//! according to a set of [`AutolinkRule`]s.
+//!
+//! Only plain text is considered: shorthands in headings, code, code blocks and inside existing links are left
+//! untouched.
+
+use pulldown_cmark::{CowStr, Event, LinkType, Tag};
+use regex::Regex;
+use std::{
+  hash::{Hash, Hasher},
+  ops::Range,
+};
+
+/// Default rules, as `(pattern, url)`.
+const DEFAULT_RULES: [(&str, &str); 3] = [
+  // owner/repo#123
+  (
+    r"(?P<owner>[A-Za-z0-9][A-Za-z0-9-]*)/(?P<repo>[\w.-]*[\w-])#(?P<number>\d+)",
+    "https://github.com/$owner/$repo/issues/$number",
+  ),
+  // #123, in Neovim; not issues of This Week in Neovim, such as TWiN #12
+  (
+    r"(?P<skip>(?i:\btwin|\bthis week in neovim)\s+)?\B#(?P<number>\d+)",
+    "https://github.com/neovim/neovim/issues/$number",
+  ),
+  // gh:owner/repo, for repositories such as plugins; bare owner/repo is too common in prose (e.g. Vim/Neovim)
+  (
+    r"gh:(?P<text>(?P<owner>[A-Za-z0-9][A-Za-z0-9-]*)/(?P<repo>[\w.-]*[\w-]))",
+    "https://github.com/$owner/$repo",
+  ),
+];
+
+/// A shorthand pattern and the link it is turned into.
+#[derive(Clone, Debug)]
+pub struct AutolinkRule {
+  regex: Regex,
+  url: String,
+}
+
+// regexes cannot be hashed, but their patterns can
+impl Hash for AutolinkRule {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.regex.as_str().hash(state);
+    self.url.hash(state);
+  }
+}
+
+impl AutolinkRule {
+  /// Create a rule linking text matching `pattern` to `url`.
+  ///
+  /// `url` can refer to the capture groups of the pattern with `$name` or `${name}`, e.g.
+  /// `https://github.com/$owner/$repo`. Matches where a capture group named `skip` participates are left untouched,
+  /// which allows excluding some contexts. If a capture group named `text` participates, only its text is kept as the
+  /// text of the link, which allows marking shorthands, such as `gh:owner/repo`.
+  pub fn new(pattern: &str, url: impl Into<String>) -> Result<Self, regex::Error> {
+    Ok(Self {
+      regex: Regex::new(pattern)?,
+      url: url.into(),
+    })
+  }
+
+  /// Find the first match starting at or after `start` that is not part of a bigger word, path or link, along with
+  /// its link and the range of the text of the link.
+  fn find(&self, text: &str, mut start: usize) -> Option<(Range<usize>, String, Range<usize>)> {
+    while let Some(captures) = self.regex.captures_at(text, start) {
+      let m = captures.get(0)?;
+
+      if m.is_empty() {
+        return None;
+      }
+
+      if captures.name("skip").is_some() {
+        start = m.end();
+        continue;
+      }
+
+      let before = text[..m.start()].chars().next_back();
+      let after = text[m.end()..].chars().next();
+      let standalone = !before.is_some_and(|c| c.is_alphanumeric() || "_/.-#@&".contains(c))
+        && !after.is_some_and(|c| c.is_alphanumeric() || "_/#".contains(c));
+
+      if standalone {
+        let mut url = String::new();
+        captures.expand(&self.url, &mut url);
+        let link_text = captures.name("text").unwrap_or(m).range();
+        return Some((m.range(), url, link_text));
+      }
+
+      start = m.start() + text[m.start()..].chars().next().map_or(1, char::len_utf8);
+    }
+
+    None
+  }
+}
+
+/// Set of rules turning shorthands into links.
+///
+/// The default rules link:
+///
+/// - `owner/repo#123` to the issue or pull request 123 of the `owner/repo` GitHub repository.
+/// - `#123` to the issue or pull request 123 of Neovim.
+/// - `gh:owner/repo` to the GitHub repository, such as `gh:phaazon/hop.nvim`; the link reads `owner/repo`.
+#[derive(Clone, Debug, Hash)]
+pub struct Autolinks {
+  rules: Vec<AutolinkRule>,
+}
+
+impl Default for Autolinks {
+  fn default() -> Self {
+    let rules = DEFAULT_RULES
+      .iter()
+      .map(|(pattern, url)| AutolinkRule::new(pattern, *url).expect("default autolink rule"))
+      .collect();
+
+    Self { rules }
+  }
+}
+
+impl Autolinks {
+  /// Create a set of rules.
+  ///
+  /// When several rules match at the same place, the first one wins.
+  pub fn new(rules: Vec<AutolinkRule>) -> Self {
+    Self { rules }
+  }
+
+  /// Disable autolinking.
+  pub fn none() -> Self {
+    Self::new(Vec::new())
+  }
+
+  /// Turn shorthands into links in a stream of Markdown events.
+  pub fn apply<'a>(&self, events: impl IntoIterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    if self.rules.is_empty() {
+      return events.into_iter().collect();
+    }
+
+    let mut linked = Vec::new();
+
+    // the parser can split text in several events, so consecutive text is merged before being searched
+    let mut text = String::new();
+
+    // depth of links, images, code blocks and headings, in which nothing is linked
+    let mut skipped = 0usize;
+
+    for event in events {
+      if let Event::Text(ref t) = event {
+        if skipped == 0 {
+          text.push_str(t);
+          continue;
+        }
+      }
+
+      self.link_text(&mut text, &mut linked);
+
+      match event {
+        Event::Start(Tag::Link(..))
+        | Event::Start(Tag::Image(..))
+        | Event::Start(Tag::CodeBlock(_))
+        | Event::Start(Tag::Heading(..)) => skipped += 1,
+
+        Event::End(Tag::Link(..))
+        | Event::End(Tag::Image(..))
+        | Event::End(Tag::CodeBlock(_))
+        | Event::End(Tag::Heading(..)) => skipped = skipped.saturating_sub(1),
+
+        _ => (),
+      }
+
+      linked.push(event);
+    }
+
+    self.link_text(&mut text, &mut linked);
+    linked
+  }
+
+  /// Emit the events of some text, with its shorthands turned into links, and clear it.
+  fn link_text(&self, text: &mut String, events: &mut Vec<Event>) {
+    if text.is_empty() {
+      return;
+    }
+
+    let mut last = 0;
+
+    while let Some((range, url, link_text)) = self.find(text, last) {
+      if range.start > last {
+        events.push(Event::Text(text[last..range.start].to_owned().into()));
+      }
+
+      let url = CowStr::from(url);
+      events.push(Event::Start(Tag::Link(
+        LinkType::Inline,
+        url.clone(),
+        "".into(),
+      )));
+      events.push(Event::Text(text[link_text].to_owned().into()));
+      events.push(Event::End(Tag::Link(LinkType::Inline, url, "".into())));
+
+      last = range.end;
+    }
+
+    if last < text.len() {
+      events.push(Event::Text(text[last..].to_owned().into()));
+    }
+
+    text.clear();
+  }
+
+  /// Find the first match of any rule, starting at or after `start`.
+  fn find(&self, text: &str, start: usize) -> Option<(Range<usize>, String, Range<usize>)> {
+    // earliest match wins; on ties, the first rule wins
+    self
+      .rules
+      .iter()
+      .filter_map(|rule| rule.find(text, start))
+      .min_by_key(|(range, ..)| range.start)
+  }
+}
@@ -0,0 +1,363 @@
+//! Weekly news read from a git repository.
+//!
+//! Instead of reading the working tree of the contents repository, a [`NewsStore`] can use a [`GitRevision`] as its
+//! [`NewsSource`], reading the `YYYY/Mon/DD` tree directly from a revision of the repository, such as
+//! `refs/heads/master`. What is served is then exactly what is committed, and rolling back is only a matter of pointing
+//! to another revision. The history of the repository also gives the date of the last change of every news.
+//!
+//! [`NewsStore`]: crate::news::NewsStore
+
+use crate::news::{
+  dir_name_to_day, file_name_to_day, LoadFailure, Month, NewsError, NewsFiles, NewsKey, NewsSource,
+  SourceEntries,
+};
+use chrono::{DateTime, TimeZone as _, Utc};
+use git2::{ObjectType, Oid, Repository, Sort, Tree, TreeEntry};
+use std::{
+  collections::BTreeMap,
+  io,
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+
+/// A revision of a git repository holding weekly news.
+///
+/// The revision is resolved every time news are listed, unless the source is pinned to a commit (see
+/// [`NewsSource::pin`]).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GitRevision {
+  /// Path of the repository (its `.git` directory for non-bare repositories).
+  repository: PathBuf,
+
+  /// Path of the root directory of the news inside the repository; empty if the news are at the top.
+  root: PathBuf,
+
+  /// Revision to read, as understood by `git rev-parse`, e.g. `refs/heads/master`, a tag or a commit.
+  revision: String,
+
+  /// Commit the revision was resolved to when pinned.
+  commit: Option<Oid>,
+}
+
+impl GitRevision {
+  /// Read news at a revision of the repository containing `path`.
+  ///
+  /// `path` is the root directory of the news, either in the working tree of the repository or the repository itself.
+  pub fn new(path: impl AsRef<Path>, revision: impl Into<String>) -> Result<Self, NewsError> {
+    let path = path.as_ref();
+    let repo = Repository::discover(path).map_err(git_error)?;
+    let root = match repo.workdir() {
+      Some(workdir) => {
+        let workdir = workdir.canonicalize()?;
+        let path = path.canonicalize()?;
+        path.strip_prefix(workdir).unwrap_or(&path).to_owned()
+      }
+
+      None => PathBuf::new(),
+    };
+
+    Ok(Self {
+      repository: repo.path().to_owned(),
+      root,
+      revision: revision.into(),
+      commit: None,
+    })
+  }
+
+  pub fn revision(&self) -> &str {
+    &self.revision
+  }
+
+  /// Resolve the commit the revision currently points to.
+  pub fn commit_id(&self) -> Result<String, NewsError> {
+    self.resolve().map(|oid| oid.to_string())
+  }
+
+  fn resolve(&self) -> Result<Oid, NewsError> {
+    let repo = self.open()?;
+    let commit = repo
+      .revparse_single(&self.revision)
+      .and_then(|object| object.peel_to_commit())
+      .map_err(git_error)?;
+
+    Ok(commit.id())
+  }
+
+  /// Commit to read from: the pinned one, if any, or the one the revision currently points to.
+  fn commit(&self) -> Result<Oid, NewsError> {
+    match self.commit {
+      Some(commit) => Ok(commit),
+      None => self.resolve(),
+    }
+  }
+
+  fn open(&self) -> Result<Repository, NewsError> {
+    Repository::open(&self.repository).map_err(git_error)
+  }
+
+  /// Tree of the root directory of the news at a commit.
+  fn root_tree<'a>(&self, repo: &'a Repository, commit: Oid) -> Result<Tree<'a>, NewsError> {
+    let tree = repo
+      .find_commit(commit)
+      .and_then(|commit| commit.tree())
+      .map_err(git_error)?;
+
+    if self.root.as_os_str().is_empty() {
+      return Ok(tree);
+    }
+
+    tree
+      .get_path(&self.root)
+      .and_then(|entry| entry.to_object(repo))
+      .and_then(|object| object.peel_to_tree())
+      .map_err(git_error)
+  }
+}
+
+impl NewsSource for GitRevision {
+  fn list(&self) -> Result<SourceEntries, NewsError> {
+    let repo = self.open()?;
+    let root = self.root_tree(&repo, self.commit()?)?;
+    let mut entries = Vec::new();
+
+    for year_entry in root.iter().filter(is_tree) {
+      let year_path = PathBuf::from(entry_name(&year_entry));
+      let year = match entry_name(&year_entry).parse::<u16>() {
+        Ok(year) => year,
+        Err(_) => {
+          entries.push(Err(LoadFailure {
+            path: year_path,
+            error: NewsError::CannotParseYear(entry_name(&year_entry)),
+          }));
+          continue;
+        }
+      };
+
+      for month_entry in subtree(&repo, &year_entry)?.iter().filter(is_tree) {
+        let month_path = year_path.join(entry_name(&month_entry));
+        let month = match entry_name(&month_entry).parse::<Month>() {
+          Ok(month) => month,
+          Err(_) => {
+            entries.push(Err(LoadFailure {
+              path: month_path,
+              error: NewsError::CannotParseMonth(entry_name(&month_entry)),
+            }));
+            continue;
+          }
+        };
+
+        for day_entry in subtree(&repo, &month_entry)?.iter() {
+          let day = match day_entry.kind() {
+            Some(ObjectType::Blob) => file_name_to_day(&entry_name(&day_entry)),
+            Some(ObjectType::Tree) => dir_name_to_day(&entry_name(&day_entry)),
+            _ => continue,
+          };
+
+          let entry = day
+            .map(|day| NewsKey { year, month, day })
+            .map_err(|error| LoadFailure {
+              path: month_path.join(entry_name(&day_entry)),
+              error,
+            });
+          entries.push(entry);
+        }
+      }
+    }
+
+    Ok(entries)
+  }
+
+  fn read(&self, key: NewsKey) -> Result<Option<NewsFiles>, LoadFailure> {
+    let to_failure = |error| LoadFailure {
+      path: dir_path(key),
+      error,
+    };
+    let repo = self.open().map_err(to_failure)?;
+    let commit = self.commit().map_err(to_failure)?;
+    let root = self.root_tree(&repo, commit).map_err(to_failure)?;
+
+    for path in [dir_path(key), file_path(key)] {
+      if let Ok(entry) = root.get_path(&path) {
+        return read_entry(&repo, &entry)
+          .map(Some)
+          .map_err(|error| LoadFailure { path, error });
+      }
+    }
+
+    Ok(None)
+  }
+
+  fn read_asset(&self, key: NewsKey, asset: &str) -> Option<Vec<u8>> {
+    let repo = self.open().ok()?;
+    let root = self.root_tree(&repo, self.commit().ok()?).ok()?;
+    let entry = root.get_path(&dir_path(key).join(asset)).ok()?;
+    let blob = repo.find_blob(entry.id()).ok()?;
+
+    Some(blob.content().to_owned())
+  }
+
+  /// Find the date of the last commit changing each news, following the first parents from the commit read from.
+  fn last_updated(&self, keys: &[NewsKey]) -> Result<BTreeMap<NewsKey, DateTime<Utc>>, NewsError> {
+    let repo = self.open()?;
+    let mut pending: BTreeMap<_, _> = keys
+      .iter()
+      .map(|&key| {
+        let paths = [
+          self.root.join(dir_path(key)),
+          self.root.join(file_path(key)),
+        ];
+        (key, paths)
+      })
+      .collect();
+    let mut dates = BTreeMap::new();
+
+    let mut revwalk = repo.revwalk().map_err(git_error)?;
+    revwalk
+      .set_sorting(Sort::TOPOLOGICAL | Sort::TIME)
+      .map_err(git_error)?;
+    revwalk.simplify_first_parent().map_err(git_error)?;
+    revwalk.push(self.commit()?).map_err(git_error)?;
+
+    for oid in revwalk {
+      if pending.is_empty() {
+        break;
+      }
+
+      let commit = repo
+        .find_commit(oid.map_err(git_error)?)
+        .map_err(git_error)?;
+      let tree = commit.tree().map_err(git_error)?;
+      let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().map_err(git_error)?),
+        Err(_) => None,
+      };
+      let date = Utc
+        .timestamp_opt(commit.time().seconds(), 0)
+        .single()
+        .unwrap_or_default();
+
+      pending.retain(|key, paths| {
+        let changed = paths.iter().any(|path| {
+          let id = |tree: &Tree| tree.get_path(path).ok().map(|entry| entry.id());
+          id(&tree) != parent_tree.as_ref().and_then(id)
+        });
+
+        if changed {
+          dates.insert(*key, date);
+        }
+
+        !changed
+      });
+    }
+
+    Ok(dates)
+  }
+
+  fn pin(&self) -> Result<Option<Arc<dyn NewsSource>>, NewsError> {
+    let commit = self.resolve()?;
+    log::debug!("reading news from {} ({})", self.revision, commit);
+
+    Ok(Some(Arc::new(Self {
+      commit: Some(commit),
+      ..self.clone()
+    })))
+  }
+
+  fn version(&self) -> Option<String> {
+    self.commit.map(|commit| commit.to_string())
+  }
+}
+
+fn git_error(e: git2::Error) -> NewsError {
+  NewsError::CannotReadRevision(e.message().to_owned())
+}
+
+fn is_tree(entry: &TreeEntry) -> bool {
+  entry.kind() == Some(ObjectType::Tree)
+}
+
+fn entry_name(entry: &TreeEntry) -> String {
+  String::from_utf8_lossy(entry.name_bytes()).into_owned()
+}
+
+fn subtree<'a>(repo: &'a Repository, entry: &TreeEntry) -> Result<Tree<'a>, NewsError> {
+  repo.find_tree(entry.id()).map_err(git_error)
+}
+
+/// Path of the directory of a news, relative to the root directory of the news.
+fn dir_path(key: NewsKey) -> PathBuf {
+  PathBuf::from(format!("{}/{}/{:02}", key.year, key.month, key.day))
+}
+
+/// Path of the single file of a news, relative to the root directory of the news.
+fn file_path(key: NewsKey) -> PathBuf {
+  PathBuf::from(format!("{}/{}/{:02}.md", key.year, key.month, key.day))
+}
+
+/// Read the files of a news, either a single Markdown file or a directory.
+fn read_entry(repo: &Repository, entry: &TreeEntry) -> Result<NewsFiles, NewsError> {
+  if entry.kind() == Some(ObjectType::Blob) {
+    return read_md(repo, entry.id()).map(NewsFiles::File);
+  }
+
+  let mut files = Vec::new();
+  collect_files(repo, &subtree(repo, entry)?, "", &mut files)?;
+
+  let mut mds = Vec::new();
+  let mut assets = Vec::new();
+
+  for (dir, name, id) in files {
+    let path = if dir.is_empty() {
+      name.clone()
+    } else {
+      format!("{}/{}", dir, name)
+    };
+
+    if name.ends_with(".md") {
+      mds.push((path, read_md(repo, id)?));
+    } else if !name.starts_with('.') {
+      assets.push(path);
+    }
+  }
+
+  Ok(NewsFiles::Dir { mds, assets })
+}
+
+/// Collect the files of a tree as `(dir, name, id)`, sorted by names and according to a DFS of the tree.
+fn collect_files(
+  repo: &Repository,
+  tree: &Tree,
+  dir: &str,
+  files: &mut Vec<(String, String, Oid)>,
+) -> Result<(), NewsError> {
+  let mut entries: Vec<_> = tree.iter().collect();
+  entries.sort_by_key(entry_name);
+
+  for entry in entries {
+    let name = entry_name(&entry);
+
+    match entry.kind() {
+      Some(ObjectType::Tree) => {
+        let subdir = if dir.is_empty() {
+          name
+        } else {
+          format!("{}/{}", dir, name)
+        };
+        collect_files(repo, &subtree(repo, &entry)?, &subdir, files)?;
+      }
+
+      Some(ObjectType::Blob) => files.push((dir.to_owned(), name, entry.id())),
+
+      _ => (),
+    }
+  }
+
+  Ok(())
+}
+
+fn read_md(repo: &Repository, id: Oid) -> Result<String, NewsError> {
+  let blob = repo.find_blob(id).map_err(git_error)?;
+
+  String::from_utf8(blob.content().to_owned())
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+}
@@ -0,0 +1,161 @@
+//! Syntax highlighting of code blocks.
+//!
+//! Fenced code blocks are highlighted when weekly news are parsed. The generated HTML uses inline styles, so that code
+//! is highlighted wherever the HTML of a news ends up (web pages, feeds, emails, etc.), without any script or
+//! stylesheet.
+
+use pulldown_cmark::{CodeBlockKind, Event, Tag};
+use std::{
+  hash::{Hash, Hasher},
+  sync::{Arc, OnceLock},
+};
+use syntect::{
+  easy::HighlightLines,
+  highlighting::{Color, Theme, ThemeSet},
+  html::{styled_line_to_highlighted_html, IncludeBackground},
+  parsing::SyntaxSet,
+  util::LinesWithEndings,
+};
+
+/// Theme used by default.
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// Syntaxes known by the highlighter; they include, among others, Lua, Vim script, shell, TOML and Rust.
+fn syntaxes() -> &'static SyntaxSet {
+  static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+  SYNTAXES.get_or_init(two_face::syntax::extra_newlines)
+}
+
+fn themes() -> &'static ThemeSet {
+  static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+  THEMES.get_or_init(ThemeSet::load_defaults)
+}
+
+/// Highlighter of fenced code blocks.
+#[derive(Clone, Debug)]
+pub struct Highlighter {
+  name: String,
+  theme: Arc<Theme>,
+}
+
+// themes are identified by their names
+impl Hash for Highlighter {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.name.hash(state);
+  }
+}
+
+impl Default for Highlighter {
+  fn default() -> Self {
+    Self::new(DEFAULT_THEME).expect("default highlighting theme")
+  }
+}
+
+impl Highlighter {
+  /// Create a highlighter using a theme, if it exists.
+  ///
+  /// See [`Highlighter::themes`] for the list of available themes.
+  pub fn new(theme: &str) -> Option<Self> {
+    let name = theme.to_owned();
+    let theme = themes().themes.get(theme)?.clone();
+    Some(Self {
+      name,
+      theme: Arc::new(theme),
+    })
+  }
+
+  /// Names of the available themes.
+  pub fn themes() -> impl Iterator<Item = &'static str> {
+    themes().themes.keys().map(String::as_str)
+  }
+
+  /// Highlight a piece of code as HTML, if the language is supported.
+  ///
+  /// The language is looked up by name or file extension, e.g. `lua`, `vim`, `sh` or `rs`.
+  pub fn highlight(&self, lang: &str, code: &str) -> Option<String> {
+    let syntax = syntaxes().find_syntax_by_token(lang)?;
+    let mut lines = HighlightLines::new(syntax, &self.theme);
+    let settings = &self.theme.settings;
+
+    let mut html = String::from("<pre class=\"highlight\" style=\"");
+    if let Some(background) = settings.background {
+      html.push_str(&format!("background-color:{};", css_color(background)));
+    }
+    if let Some(foreground) = settings.foreground {
+      html.push_str(&format!("color:{};", css_color(foreground)));
+    }
+    html.push_str("\"><code class=\"language-");
+    pulldown_cmark::escape::escape_html(&mut html, lang).ok()?;
+    html.push_str("\">");
+
+    for line in LinesWithEndings::from(code) {
+      let regions = lines.highlight_line(line, syntaxes()).ok()?;
+      html.push_str(&styled_line_to_highlighted_html(&regions, IncludeBackground::No).ok()?);
+    }
+
+    html.push_str("</code></pre>\n");
+    Some(html)
+  }
+
+  /// Highlight the fenced code blocks of a stream of Markdown events.
+  ///
+  /// Code blocks without a language or with an unsupported language are left untouched.
+  pub fn apply<'a>(&self, events: impl IntoIterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let mut highlighted = Vec::new();
+    let mut events = events.into_iter();
+
+    while let Some(event) = events.next() {
+      let info = match event {
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) => info.clone(),
+        _ => {
+          highlighted.push(event);
+          continue;
+        }
+      };
+
+      let inner: Vec<_> = events
+        .by_ref()
+        .take_while(|event| !matches!(event, Event::End(Tag::CodeBlock(_))))
+        .collect();
+      let code: String = inner
+        .iter()
+        .filter_map(|event| match event {
+          Event::Text(text) => Some(text.as_ref()),
+          _ => None,
+        })
+        .collect();
+
+      // the info string can contain more than the language, e.g. ```lua title="init.lua"
+      let html = info
+        .split_whitespace()
+        .next()
+        .and_then(|lang| self.highlight(lang, &code));
+
+      match html {
+        Some(html) => highlighted.push(Event::Html(html.into())),
+
+        None => {
+          highlighted.push(event);
+          highlighted.extend(inner);
+          highlighted.push(Event::End(Tag::CodeBlock(CodeBlockKind::Fenced(info))));
+        }
+      }
+    }
+
+    highlighted
+  }
+}
+
+fn css_color(color: Color) -> String {
+  if color.a == 0xFF {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+  } else {
+    format!(
+      "rgba({}, {}, {}, {:.2})",
+      color.r,
+      color.g,
+      color.b,
+      f32::from(color.a) / 255.
+    )
+  }
+}
@@ -0,0 +1,722 @@
+//! Structured representation of a weekly news.
+//!
+//! A weekly news is parsed into a tree: the [`Issue`] is made of [`Section`]s (e.g. _Core_ or _Plugins_), which can
+//! contain subsections and [`Item`]s. The HTML of the news is rendered from that tree.
+//!
+//! Headings define the structure of the tree:
+//!
+//! - Level 1 and 2 headings open sections. A level 2 heading is a subsection of the previous level 1 section, if any.
+//! - Level 3 headings open items, in the last opened section.
+//! - Deeper headings are part of the body of the current item.
+//!
+//! Every heading is given a stable identifier, used as an anchor, and all headings are collected in a table of
+//! contents.
+//!
+//! Parts of an issue can be extracted from the tree, e.g. to only keep its _Core_ section or the items tagged with a
+//! class (`### Some item {.lsp}`).
+
+use pulldown_cmark::{html, Event, HeadingLevel, Tag};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Deepest heading level opening a section; the next level opens items.
+const SECTION_MAX_LEVEL: u8 = 2;
+
+/// Level of headings opening items.
+const ITEM_LEVEL: u8 = SECTION_MAX_LEVEL + 1;
+
+/// A weekly news, as a tree of sections and items.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Issue {
+  /// HTML content appearing before any heading.
+  pub intro: String,
+
+  /// Every link found in the intro.
+  pub intro_links: Vec<Link>,
+
+  /// Items appearing before any section.
+  pub items: Vec<Item>,
+
+  /// Top-level sections.
+  pub sections: Vec<Section>,
+}
+
+/// A section of a weekly news, such as _Core_ or _Plugins_.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Section {
+  pub heading: Heading,
+
+  /// HTML content between the heading and the first item or subsection.
+  pub body: String,
+
+  /// Every link found in the heading and the body of the section, excluding its items and subsections.
+  pub links: Vec<Link>,
+
+  pub items: Vec<Item>,
+  pub subsections: Vec<Section>,
+}
+
+/// A single piece of news, such as a new plugin or a change in Neovim core.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Item {
+  pub heading: Heading,
+
+  /// HTML content of the item, without its heading.
+  pub body: String,
+
+  /// Every link found in the heading and the body of the item.
+  pub links: Vec<Link>,
+
+  /// Person who contributed the item, if mentioned.
+  pub contributor: Option<Contributor>,
+}
+
+/// Heading of a section or item.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Heading {
+  /// Level of the heading, from 1 to 6.
+  pub level: u8,
+
+  /// Identifier of the heading, unique in the issue.
+  ///
+  /// It is either set explicitly in the Markdown (`# Heading {#id}`) or derived from the text of the heading, and
+  /// suffixed with a number if already used (e.g. `fix-1`).
+  pub id: String,
+
+  /// Classes of the heading.
+  pub classes: Vec<String>,
+
+  /// Plain text version of the heading.
+  pub text: String,
+
+  /// HTML content of the heading.
+  pub html: String,
+}
+
+/// A link found in a weekly news.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Link {
+  pub url: String,
+  pub text: String,
+}
+
+/// Contributor of an item.
+///
+/// Contributors are mentioned with a link whose text starts with `@`, such as `[@phaazon](https://github.com/phaazon)`.
+/// The last mention in the body of an item is the contributor.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Contributor {
+  pub name: String,
+  pub url: String,
+}
+
+/// Entry of a table of contents.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TocEntry {
+  /// Level of the heading, from 1 to 6.
+  pub level: u8,
+
+  /// Identifier of the heading, to link to.
+  pub id: String,
+
+  /// Plain text version of the heading.
+  pub title: String,
+
+  /// Entries of deeper headings, until the next heading of the same level or above.
+  pub children: Vec<TocEntry>,
+}
+
+/// Part of a weekly news: a whole section or a single item.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IssuePart<'a> {
+  Section(&'a Section),
+  Item(&'a Item),
+}
+
+impl IssuePart<'_> {
+  pub fn heading(&self) -> &Heading {
+    match self {
+      IssuePart::Section(section) => &section.heading,
+      IssuePart::Item(item) => &item.heading,
+    }
+  }
+
+  /// Render the part as HTML, as it appears in the whole issue.
+  pub fn to_html(&self) -> String {
+    let mut html = String::new();
+
+    match self {
+      IssuePart::Section(section) => section.push_html(&mut html),
+      IssuePart::Item(item) => item.push_html(&mut html),
+    }
+
+    html
+  }
+}
+
+impl Issue {
+  /// Build an issue from a stream of Markdown events.
+  pub fn from_events<'a>(events: impl IntoIterator<Item = Event<'a>>) -> Self {
+    Self::from_events_with_toc(events).0
+  }
+
+  /// Build an issue and its table of contents from a stream of Markdown events.
+  pub fn from_events_with_toc<'a>(
+    events: impl IntoIterator<Item = Event<'a>>,
+  ) -> (Self, Vec<TocEntry>) {
+    let mut builder = IssueBuilder::new();
+    let mut ids = HashSet::new();
+    let mut toc = Vec::new();
+    let mut events = events.into_iter();
+
+    // depth of the containers (block quotes, lists, etc.) the current event is in
+    let mut depth = 0_usize;
+
+    while let Some(event) = events.next() {
+      match event {
+        Event::Start(Tag::Heading(level, id, classes)) => {
+          let inner: Vec<_> = events
+            .by_ref()
+            .take_while(|event| !matches!(event, Event::End(Tag::Heading(..))))
+            .collect();
+          let level = heading_level(level);
+          let heading_links = links(&inner);
+          let heading = Heading::new(level, id, classes, &inner, &mut ids);
+
+          toc.push(TocEntry {
+            level,
+            id: heading.id.clone(),
+            title: heading.text.clone(),
+            children: Vec::new(),
+          });
+
+          // only top-level headings split the issue; the others would split their containers
+          if depth == 0 {
+            builder.open(heading, heading_links, inner);
+          } else {
+            builder.push_heading(&heading, inner);
+          }
+        }
+
+        event => {
+          match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => (),
+          }
+
+          builder.push(event);
+        }
+      }
+    }
+
+    (builder.finish(), nest_toc(toc))
+  }
+
+  /// Find the outermost sections about a topic, such as `core` or `plugins`.
+  ///
+  /// See [`Heading::is_about`] for how topics are matched.
+  pub fn sections_about(&self, topic: &str) -> Vec<&Section> {
+    let mut sections = Vec::new();
+    let mut pending: Vec<_> = self.sections.iter().rev().collect();
+
+    while let Some(section) = pending.pop() {
+      if section.heading.is_about(topic) {
+        sections.push(section);
+      } else {
+        pending.extend(section.subsections.iter().rev());
+      }
+    }
+
+    sections
+  }
+
+  /// Whether a section is named after a topic; see [`Heading::is_named`].
+  ///
+  /// Unlike [`Issue::sections_about`], words of a heading given in another order don’t name it.
+  pub fn has_section_named(&self, topic: &str) -> bool {
+    let mut pending: Vec<_> = self.sections.iter().collect();
+
+    while let Some(section) = pending.pop() {
+      if section.heading.is_named(topic) {
+        return true;
+      }
+
+      pending.extend(&section.subsections);
+    }
+
+    false
+  }
+
+  /// Find the outermost sections and items tagged with a class, in the order they appear.
+  pub fn parts_tagged(&self, tag: &str) -> Vec<IssuePart<'_>> {
+    let mut parts = Vec::new();
+
+    for item in &self.items {
+      if item.heading.has_class(tag) {
+        parts.push(IssuePart::Item(item));
+      }
+    }
+
+    for section in &self.sections {
+      section.collect_tagged(tag, &mut parts);
+    }
+
+    parts
+  }
+
+  /// Render the issue as HTML.
+  pub fn to_html(&self) -> String {
+    let mut html = self.intro.clone();
+
+    for item in &self.items {
+      item.push_html(&mut html);
+    }
+
+    for section in &self.sections {
+      section.push_html(&mut html);
+    }
+
+    html
+  }
+}
+
+impl Section {
+  fn collect_tagged<'a>(&'a self, tag: &str, parts: &mut Vec<IssuePart<'a>>) {
+    if self.heading.has_class(tag) {
+      parts.push(IssuePart::Section(self));
+      return;
+    }
+
+    for item in &self.items {
+      if item.heading.has_class(tag) {
+        parts.push(IssuePart::Item(item));
+      }
+    }
+
+    for subsection in &self.subsections {
+      subsection.collect_tagged(tag, parts);
+    }
+  }
+
+  /// Set the HTML of the heading and body of the section and of everything it contains, in the order they appear.
+  fn fill_html(&mut self, html: &mut impl Iterator<Item = String>) {
+    self.heading.html = html.next().unwrap_or_default();
+    self.body = html.next().unwrap_or_default();
+
+    for item in &mut self.items {
+      item.fill_html(html);
+    }
+
+    for subsection in &mut self.subsections {
+      subsection.fill_html(html);
+    }
+  }
+
+  fn push_html(&self, html: &mut String) {
+    self.heading.push_html(html);
+    html.push_str(&self.body);
+
+    for item in &self.items {
+      item.push_html(html);
+    }
+
+    for subsection in &self.subsections {
+      subsection.push_html(html);
+    }
+  }
+}
+
+impl Item {
+  /// Set the HTML of the heading and body of the item.
+  fn fill_html(&mut self, html: &mut impl Iterator<Item = String>) {
+    self.heading.html = html.next().unwrap_or_default();
+    self.body = html.next().unwrap_or_default();
+  }
+
+  fn push_html(&self, html: &mut String) {
+    self.heading.push_html(html);
+    html.push_str(&self.body);
+  }
+}
+
+impl Heading {
+  /// Whether the heading is about a topic.
+  ///
+  /// That is the case if the topic is one of its classes, or if all the words of the topic are found in its text,
+  /// ignoring case; e.g. `core` is the topic of _Neovim core_.
+  pub fn is_about(&self, topic: &str) -> bool {
+    if self.has_class(topic) {
+      return true;
+    }
+
+    let slug = slugify(&self.text);
+    let words: HashSet<_> = slug.split('-').collect();
+    slugify(topic).split('-').all(|word| words.contains(word))
+  }
+
+  /// Whether the heading is named after a topic.
+  ///
+  /// That is the case if the topic is one of its classes, or if it is its text or one of its words, ignoring case;
+  /// e.g. _Neovim core_ is named after `core` and `neovim-core`, but not `core-neovim`.
+  pub fn is_named(&self, topic: &str) -> bool {
+    if self.has_class(topic) {
+      return true;
+    }
+
+    let slug = slugify(&self.text);
+    let topic = slugify(topic);
+    slug == topic || slug.split('-').any(|word| word == topic)
+  }
+
+  /// Whether the heading has a class, ignoring case.
+  pub fn has_class(&self, class: &str) -> bool {
+    self.classes.iter().any(|c| c.eq_ignore_ascii_case(class))
+  }
+
+  /// Create a heading from its inner events; its HTML is rendered later, along with the rest of the issue.
+  fn new(
+    level: u8,
+    id: Option<&str>,
+    classes: Vec<&str>,
+    inner: &[Event],
+    ids: &mut HashSet<String>,
+  ) -> Self {
+    let text = plain_text(inner);
+
+    // explicit identifiers are de-duplicated as well, so that every anchor stays reachable
+    let id = unique_id(id.map_or_else(|| slugify(&text), str::to_owned), ids);
+    ids.insert(id.clone());
+
+    Self {
+      level,
+      id,
+      classes: classes.into_iter().map(str::to_owned).collect(),
+      text,
+      html: String::new(),
+    }
+  }
+
+  /// Opening tag of the heading, with its identifier and classes.
+  fn open_tag(&self) -> String {
+    let mut html = format!("<h{} id=\"", self.level);
+    escape_html(&mut html, &self.id);
+    html.push('"');
+
+    if !self.classes.is_empty() {
+      html.push_str(" class=\"");
+      escape_html(&mut html, &self.classes.join(" "));
+      html.push('"');
+    }
+
+    html.push('>');
+    html
+  }
+
+  fn close_tag(&self) -> String {
+    format!("</h{}>\n", self.level)
+  }
+
+  fn push_html(&self, html: &mut String) {
+    html.push_str(&self.open_tag());
+    html.push_str(&self.html);
+    html.push_str(&self.close_tag());
+  }
+}
+
+/// Node of the tree currently being built, along with the index of the fragment holding the events of its body.
+enum Node {
+  Section(Section, usize),
+  Item(Item, usize),
+}
+
+struct IssueBuilder<'a> {
+  issue: Issue,
+
+  // events of the intro, then of the heading and the body of every node, in the order they appear in the issue; they
+  // are rendered together, so that footnotes are numbered across the whole issue
+  fragments: Vec<Vec<Event<'a>>>,
+
+  // stack of opened nodes; sections are sorted by increasing levels and an item can only be on top
+  stack: Vec<Node>,
+}
+
+impl<'a> IssueBuilder<'a> {
+  fn new() -> Self {
+    Self {
+      issue: Issue::default(),
+      fragments: vec![Vec::new()],
+      stack: Vec::new(),
+    }
+  }
+
+  fn push(&mut self, event: Event<'a>) {
+    let fragment = match self.stack.last() {
+      Some(Node::Section(_, fragment)) | Some(Node::Item(_, fragment)) => *fragment,
+      None => 0,
+    };
+
+    self.fragments[fragment].push(event);
+  }
+
+  /// Push a heading that is part of the current body.
+  fn push_heading(&mut self, heading: &Heading, inner: Vec<Event<'a>>) {
+    self.push(Event::Html(heading.open_tag().into()));
+
+    for event in inner {
+      self.push(event);
+    }
+
+    self.push(Event::Html(heading.close_tag().into()));
+  }
+
+  fn open(&mut self, heading: Heading, heading_links: Vec<Link>, inner: Vec<Event<'a>>) {
+    if heading.level > ITEM_LEVEL {
+      // deeper headings are part of the current body
+      self.push_heading(&heading, inner);
+      return;
+    }
+
+    // close everything that cannot contain the new heading
+    while let Some(node) = self.stack.last() {
+      let contains = match node {
+        Node::Section(section, _) => section.heading.level < heading.level,
+        Node::Item(..) => false,
+      };
+
+      if contains {
+        break;
+      }
+
+      self.close();
+    }
+
+    self.fragments.push(inner);
+    let body = self.fragments.len();
+    self.fragments.push(Vec::new());
+
+    let node = if heading.level == ITEM_LEVEL {
+      Node::Item(
+        Item {
+          heading,
+          body: String::new(),
+          links: heading_links,
+          contributor: None,
+        },
+        body,
+      )
+    } else {
+      Node::Section(
+        Section {
+          heading,
+          body: String::new(),
+          links: heading_links,
+          items: Vec::new(),
+          subsections: Vec::new(),
+        },
+        body,
+      )
+    };
+
+    self.stack.push(node);
+  }
+
+  /// Close the node on top of the stack and attach it to its parent.
+  fn close(&mut self) {
+    let node = match self.stack.pop() {
+      Some(node) => node,
+      None => return,
+    };
+
+    let parent = self.stack.last_mut().map(|node| match node {
+      Node::Section(section, _) => section,
+      Node::Item(..) => unreachable!("items cannot contain other nodes"),
+    });
+
+    match node {
+      Node::Section(mut section, body) => {
+        section.links.extend(links(&self.fragments[body]));
+
+        match parent {
+          Some(parent) => parent.subsections.push(section),
+          None => self.issue.sections.push(section),
+        }
+      }
+
+      Node::Item(mut item, body) => {
+        let body_links = links(&self.fragments[body]);
+        item.contributor = contributor(&body_links);
+        item.links.extend(body_links);
+
+        match parent {
+          Some(parent) => parent.items.push(item),
+          None => self.issue.items.push(item),
+        }
+      }
+    }
+  }
+
+  fn finish(mut self) -> Issue {
+    while !self.stack.is_empty() {
+      self.close();
+    }
+
+    self.issue.intro_links = links(&self.fragments[0]);
+
+    // items appearing before any section come first, and sections contain their items before their subsections, so
+    // walking the tree visits the fragments in the order they were opened
+    let mut html = render_fragments(self.fragments).into_iter();
+    self.issue.intro = html.next().unwrap_or_default();
+
+    for item in &mut self.issue.items {
+      item.fill_html(&mut html);
+    }
+
+    for section in &mut self.issue.sections {
+      section.fill_html(&mut html);
+    }
+
+    self.issue
+  }
+}
+
+/// Separator of the fragments of an issue while they are rendered; Markdown cannot produce NUL characters.
+///
+/// It ends with a newline, so that the HTML writer starts the next fragment as if it were rendered on its own.
+const FRAGMENT_SEPARATOR: &str = "\0twin-fragment\0\n";
+
+/// Render fragments of Markdown events with a single HTML writer, returning the HTML of each fragment.
+fn render_fragments(fragments: Vec<Vec<Event>>) -> Vec<String> {
+  let events = fragments.into_iter().enumerate().flat_map(|(i, events)| {
+    let separator = (i > 0).then(|| Event::Html(FRAGMENT_SEPARATOR.into()));
+    separator.into_iter().chain(events)
+  });
+
+  let mut html = String::new();
+  html::push_html(&mut html, events);
+  html.split(FRAGMENT_SEPARATOR).map(str::to_owned).collect()
+}
+
+/// Turn the text of a heading into an identifier.
+///
+/// Letters and digits are lowercased, whitespace and dashes become single dashes and everything else is dropped.
+pub fn slugify(text: &str) -> String {
+  let mut slug = String::with_capacity(text.len());
+
+  for c in text.chars() {
+    if c.is_alphanumeric() || c == '_' {
+      slug.extend(c.to_lowercase());
+    } else if (c.is_whitespace() || c == '-') && !slug.is_empty() && !slug.ends_with('-') {
+      slug.push('-');
+    }
+  }
+
+  let slug = slug.trim_end_matches('-');
+
+  if slug.is_empty() {
+    "section".to_owned()
+  } else {
+    slug.to_owned()
+  }
+}
+
+/// De-duplicate an identifier by suffixing it with a number if it’s already used.
+fn unique_id(id: String, ids: &HashSet<String>) -> String {
+  if !ids.contains(&id) {
+    return id;
+  }
+
+  (1..)
+    .map(|n| format!("{}-{}", id, n))
+    .find(|candidate| !ids.contains(candidate))
+    .expect("unique identifier")
+}
+
+/// Nest a flat list of entries according to their levels.
+fn nest_toc(entries: Vec<TocEntry>) -> Vec<TocEntry> {
+  fn pop(stack: &mut Vec<TocEntry>, roots: &mut Vec<TocEntry>) {
+    if let Some(entry) = stack.pop() {
+      match stack.last_mut() {
+        Some(parent) => parent.children.push(entry),
+        None => roots.push(entry),
+      }
+    }
+  }
+
+  let mut roots = Vec::new();
+  let mut stack: Vec<TocEntry> = Vec::new();
+
+  for entry in entries {
+    while matches!(stack.last(), Some(top) if top.level >= entry.level) {
+      pop(&mut stack, &mut roots);
+    }
+
+    stack.push(entry);
+  }
+
+  while !stack.is_empty() {
+    pop(&mut stack, &mut roots);
+  }
+
+  roots
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+  match level {
+    HeadingLevel::H1 => 1,
+    HeadingLevel::H2 => 2,
+    HeadingLevel::H3 => 3,
+    HeadingLevel::H4 => 4,
+    HeadingLevel::H5 => 5,
+    HeadingLevel::H6 => 6,
+  }
+}
+
+/// Extract the plain text of some events.
+fn plain_text(events: &[Event]) -> String {
+  events
+    .iter()
+    .filter_map(|event| match event {
+      Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+      Event::SoftBreak | Event::HardBreak => Some(" "),
+      _ => None,
+    })
+    .collect()
+}
+
+/// Extract all the links of some events.
+fn links(events: &[Event]) -> Vec<Link> {
+  let mut links = Vec::new();
+  let mut events = events.iter();
+
+  while let Some(event) = events.next() {
+    if let Event::Start(Tag::Link(_, url, _)) = event {
+      let inner: Vec<_> = events
+        .by_ref()
+        .take_while(|event| !matches!(event, Event::End(Tag::Link(..))))
+        .cloned()
+        .collect();
+
+      links.push(Link {
+        url: url.to_string(),
+        text: plain_text(&inner),
+      });
+    }
+  }
+
+  links
+}
+
+fn contributor(links: &[Link]) -> Option<Contributor> {
+  links.iter().rev().find_map(|link| {
+    let name = link.text.strip_prefix('@')?;
+    Some(Contributor {
+      name: name.to_owned(),
+      url: link.url.clone(),
+    })
+  })
+}
+
+fn escape_html(html: &mut String, text: &str) {
+  pulldown_cmark::escape::escape_html(html, text).expect("HTML escaping");
+}
@@ -1 +1,12 @@
+pub mod assets;
+pub mod autolink;
+pub mod git;
+pub mod highlight;
+pub mod issue;
 pub mod news;
+pub mod numbers;
+pub mod plugins;
+pub mod scaffold;
+pub mod search;
+pub mod snapshot;
+pub mod validate;
@@ -0,0 +1,144 @@
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{path::PathBuf, process::exit};
+use twin::{
+  numbers::IssueNumbers,
+  scaffold::{scaffold, Layout},
+  validate::{validate, ValidationOptions},
+};
+
+/// Tools to work with the contents of This Week in Neovim.
+#[derive(Debug, Parser)]
+#[command(name = "twin", version, about)]
+struct Cli {
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Validate a contents tree, reporting every problem found.
+  ///
+  /// Exit with a non-zero status if any problem is found.
+  Validate {
+    /// Root directory of the contents, containing the year directories, or a tar archive of it.
+    root: PathBuf,
+
+    /// Section every weekly news must have; can be passed several times.
+    #[arg(long = "require-section", value_name = "NAME")]
+    required_sections: Option<Vec<String>>,
+  },
+
+  /// Create the next weekly news, with the standard sections and a front matter to fill.
+  ///
+  /// An existing news is never overwritten.
+  New {
+    /// Root directory of the contents, containing the year directories.
+    root: PathBuf,
+
+    /// Date of the news (YYYY-MM-DD); defaults to one week after the latest news.
+    #[arg(long)]
+    date: Option<NaiveDate>,
+
+    /// Layout of the news on disk.
+    #[arg(long, value_enum, default_value_t = LayoutArg::Dir)]
+    layout: LayoutArg,
+
+    /// File of the issue numbers persisted by the backend (its numbers_path), so that numbers already taken are
+    /// never proposed again.
+    #[arg(long, value_name = "PATH")]
+    numbers: Option<PathBuf>,
+  },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum LayoutArg {
+  /// A single Markdown file, e.g. 2022/Jun/27.md.
+  File,
+
+  /// A directory with one Markdown file per section, e.g. 2022/Jun/27/….
+  Dir,
+}
+
+impl From<LayoutArg> for Layout {
+  fn from(layout: LayoutArg) -> Self {
+    match layout {
+      LayoutArg::File => Layout::File,
+      LayoutArg::Dir => Layout::Directory,
+    }
+  }
+}
+
+fn main() {
+  let cli = Cli::parse();
+
+  match cli.command {
+    Command::Validate {
+      root,
+      required_sections,
+    } => {
+      let mut options = ValidationOptions::default();
+
+      if let Some(required_sections) = required_sections {
+        options.required_sections = required_sections;
+      }
+
+      run_validate(root, &options);
+    }
+
+    Command::New {
+      root,
+      date,
+      layout,
+      numbers,
+    } => run_new(root, date, layout.into(), numbers),
+  }
+}
+
+fn run_validate(root: PathBuf, options: &ValidationOptions) {
+  let problems = match validate(&root, options) {
+    Ok(problems) => problems,
+    Err(err) => {
+      eprintln!("cannot validate {}: {}", root.display(), err);
+      exit(2);
+    }
+  };
+
+  for problem in &problems {
+    println!("{}", problem);
+  }
+
+  if !problems.is_empty() {
+    eprintln!("{} problem(s) found", problems.len());
+    exit(1);
+  }
+}
+
+fn run_new(root: PathBuf, date: Option<NaiveDate>, layout: Layout, numbers: Option<PathBuf>) {
+  let numbers = match numbers {
+    Some(path) => IssueNumbers::load(&path).unwrap_or_else(|err| {
+      eprintln!("cannot load issue numbers {}: {}", path.display(), err);
+      exit(2);
+    }),
+    None => IssueNumbers::default(),
+  };
+
+  match scaffold(&root, date, layout, numbers) {
+    Ok(scaffolded) => {
+      let key = scaffolded.key;
+      println!(
+        "created This Week in Neovim #{} ({} {} {}):",
+        scaffolded.number, key.day, key.month, key.year
+      );
+
+      for file in scaffolded.files {
+        println!("  {}", file.display());
+      }
+    }
+
+    Err(err) => {
+      eprintln!("cannot create news: {}", err);
+      exit(1);
+    }
+  }
+}
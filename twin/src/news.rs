@@ -1,12 +1,31 @@
+mod source;
+
+pub use self::source::{FsSource, MemorySource, NewsSource, SourceEntries, TarSource};
+
+use crate::{
+  assets::Assets,
+  autolink::Autolinks,
+  highlight::Highlighter,
+  issue::{Issue, TocEntry},
+  numbers::IssueNumbers,
+  plugins::PluginIndex,
+  search::SearchIndex,
+  snapshot::{self, Snapshot, SnapshotEntry},
+};
+use arc_swap::ArcSwap;
+use chrono::{DateTime, Datelike as _, FixedOffset, NaiveDate, Utc};
+use pulldown_cmark::{CowStr, Event, Tag};
+use rayon::prelude::*;
 use serde::{de::IntoDeserializer, Deserialize, Serialize};
 use std::{
-  collections::HashMap,
+  collections::{BTreeMap, BTreeSet},
+  env,
   fmt::Display,
-  fs::{self, DirEntry},
-  io,
+  fs, io, mem,
+  ops::Bound,
   path::{Path, PathBuf},
   str::FromStr,
-  sync::{Arc, RwLock},
+  sync::{Arc, Mutex},
 };
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -25,6 +44,7 @@ pub enum Month {
   Dec,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Month {
   fn default() -> Self {
     Month::Jan
@@ -37,6 +57,34 @@ impl Display for Month {
   }
 }
 
+impl Month {
+  /// All the months, in order.
+  pub const ALL: [Month; 12] = [
+    Month::Jan,
+    Month::Feb,
+    Month::Mar,
+    Month::Apr,
+    Month::May,
+    Month::Jun,
+    Month::Jul,
+    Month::Aug,
+    Month::Sep,
+    Month::Oct,
+    Month::Nov,
+    Month::Dec,
+  ];
+
+  /// Number of the month, starting at 1 for January.
+  pub fn number(self) -> u32 {
+    self as u32 + 1
+  }
+
+  /// Get a month from its number, starting at 1 for January.
+  pub fn from_number(number: u32) -> Option<Self> {
+    Self::ALL.get(number.checked_sub(1)? as usize).copied()
+  }
+}
+
 impl FromStr for Month {
   type Err = serde::de::value::Error;
 
@@ -51,6 +99,10 @@ pub enum NewsError {
   CannotParseYear(String),
   CannotParseMonth(String),
   CannotParseDay(String),
+  CannotParseFrontMatter(String),
+  CannotParseNumbers(String),
+  CannotReadRevision(String),
+  InvalidSnapshot(String),
 }
 
 impl Display for NewsError {
@@ -60,6 +112,42 @@ impl Display for NewsError {
       NewsError::CannotParseYear(p) => write!(f, "cannot parse year directory: {}", p),
       NewsError::CannotParseMonth(p) => write!(f, "cannot parse month directory: {}", p),
       NewsError::CannotParseDay(p) => write!(f, "cannot parse day file: {}", p),
+      NewsError::CannotParseFrontMatter(e) => write!(f, "cannot parse front matter: {}", e),
+      NewsError::CannotParseNumbers(e) => write!(f, "cannot parse issue numbers: {}", e),
+      NewsError::CannotReadRevision(e) => write!(f, "cannot read git revision: {}", e),
+      NewsError::InvalidSnapshot(e) => write!(f, "invalid snapshot: {}", e),
+    }
+  }
+}
+
+impl NewsError {
+  /// Name of the error variant.
+  pub fn kind(&self) -> &'static str {
+    match self {
+      NewsError::IOError(_) => "IOError",
+      NewsError::CannotParseYear(_) => "CannotParseYear",
+      NewsError::CannotParseMonth(_) => "CannotParseMonth",
+      NewsError::CannotParseDay(_) => "CannotParseDay",
+      NewsError::CannotParseFrontMatter(_) => "CannotParseFrontMatter",
+      NewsError::CannotParseNumbers(_) => "CannotParseNumbers",
+      NewsError::CannotReadRevision(_) => "CannotReadRevision",
+      NewsError::InvalidSnapshot(_) => "InvalidSnapshot",
+    }
+  }
+}
+
+// io::Error is not Clone, so we rebuild it from its kind and message
+impl Clone for NewsError {
+  fn clone(&self) -> Self {
+    match self {
+      NewsError::IOError(e) => NewsError::IOError(io::Error::new(e.kind(), e.to_string())),
+      NewsError::CannotParseYear(p) => NewsError::CannotParseYear(p.clone()),
+      NewsError::CannotParseMonth(p) => NewsError::CannotParseMonth(p.clone()),
+      NewsError::CannotParseDay(p) => NewsError::CannotParseDay(p.clone()),
+      NewsError::CannotParseFrontMatter(e) => NewsError::CannotParseFrontMatter(e.clone()),
+      NewsError::CannotParseNumbers(e) => NewsError::CannotParseNumbers(e.clone()),
+      NewsError::CannotReadRevision(e) => NewsError::CannotReadRevision(e.clone()),
+      NewsError::InvalidSnapshot(e) => NewsError::InvalidSnapshot(e.clone()),
     }
   }
 }
@@ -72,48 +160,329 @@ impl From<io::Error> for NewsError {
 
 /// A weekly news.
 ///
-/// It contains the HTML version of the news, as well as optional previous news and next news (keys).
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+/// It contains the structured [`Issue`], its HTML rendering and table of contents, the metadata of the news, as well as
+/// optional previous news and next news (keys).
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct News {
   pub html: String,
+  pub issue: Issue,
+  pub toc: Vec<TocEntry>,
+  pub metadata: NewsMetadata,
+  pub assets: Assets,
+
+  /// Date of the last change of the news, when known (e.g. its last commit, when read from a git revision).
+  pub last_updated: Option<DateTime<Utc>>,
+
   pub prev: Option<NewsKey>,
   pub next: Option<NewsKey>,
 }
 
 impl News {
   /// Parse a [`News`] from a single Markdown-formatted file.
-  pub fn parse_from_md(md: impl AsRef<str>) -> Self {
+  ///
+  /// The file can start with a front matter block, holding the [`NewsMetadata`] of the news. It is either encoded as
+  /// TOML, delimited by `+++` lines, or as YAML, delimited by `---` lines.
+  pub fn parse_from_md(md: impl AsRef<str>) -> Result<Self, NewsError> {
+    Self::parse_from_md_with(md, &ParseOptions::default())
+  }
+
+  /// Same as [`News::parse_from_md`], with custom parse options.
+  pub fn parse_from_md_with(
+    md: impl AsRef<str>,
+    options: &ParseOptions,
+  ) -> Result<Self, NewsError> {
+    Self::parse_from_md_files_with([md.as_ref()], options)
+  }
+
+  /// Parse a [`News`] from several Markdown-formatted files, glued in order.
+  ///
+  /// Each file can have its own front matter; see [`NewsMetadata::merge`] for how they are combined.
+  pub fn parse_from_md_files<'a>(
+    mds: impl IntoIterator<Item = &'a str>,
+  ) -> Result<Self, NewsError> {
+    Self::parse_from_md_files_with(mds, &ParseOptions::default())
+  }
+
+  /// Same as [`News::parse_from_md_files`], with custom parse options.
+  pub fn parse_from_md_files_with<'a>(
+    mds: impl IntoIterator<Item = &'a str>,
+    options: &ParseOptions,
+  ) -> Result<Self, NewsError> {
+    Self::parse_from_md_files_with_assets(
+      mds.into_iter().map(|md| ("", md)),
+      Assets::default(),
+      options,
+    )
+  }
+
+  /// Parse a [`News`] from several Markdown-formatted files of a directory, glued in order, along with its assets.
+  ///
+  /// Files are given as `(dir, md)`, where `dir` is the directory of the file relative to the directory of the news.
+  /// Relative links and images pointing to assets are rewritten to the URLs of the assets.
+  pub fn parse_from_md_files_with_assets<'a>(
+    mds: impl IntoIterator<Item = (&'a str, &'a str)>,
+    assets: Assets,
+    options: &ParseOptions,
+  ) -> Result<Self, NewsError> {
+    let mut metadata = NewsMetadata::default();
+    let mut contents = Vec::new();
+
+    for (dir, md) in mds {
+      let (file_metadata, content) = split_front_matter(md)?;
+      metadata.merge(file_metadata);
+      contents.push((dir, content));
+    }
+
     let opts = pulldown_cmark::Options::all();
-    let parser = pulldown_cmark::Parser::new_ext(md.as_ref(), opts);
+    let events = contents.iter().flat_map(|(dir, content)| {
+      pulldown_cmark::Parser::new_ext(content, opts)
+        .map(|event| rewrite_asset_links(event, dir, &assets))
+    });
+    let mut events = options.autolinks.apply(events);
 
-    let mut html = String::new();
-    pulldown_cmark::html::push_html(&mut html, parser);
+    if let Some(ref highlighter) = options.highlighter {
+      events = highlighter.apply(events);
+    }
+
+    let (issue, toc) = Issue::from_events_with_toc(events);
+    let html = issue.to_html();
 
-    News {
+    Ok(News {
       html,
+      issue,
+      toc,
+      metadata,
+      assets,
+      last_updated: None,
       prev: None,
       next: None,
-    }
+    })
   }
 
   /// Parse a [`News`] by first loading a file and then parsing its content.
   pub fn load_from_md(path: impl AsRef<Path>) -> Result<Self, NewsError> {
+    Self::load_from_md_with(path, &ParseOptions::default())
+  }
+
+  /// Same as [`News::load_from_md`], with custom parse options.
+  pub fn load_from_md_with(
+    path: impl AsRef<Path>,
+    options: &ParseOptions,
+  ) -> Result<Self, NewsError> {
     let content = fs::read_to_string(path)?;
-    let news = Self::parse_from_md(&content);
-    Ok(news)
+    Self::parse_from_md_with(&content, options)
+  }
+}
+
+/// Rewrite the destination of a link or an image if it points to an asset.
+fn rewrite_asset_links<'a>(event: Event<'a>, dir: &str, assets: &Assets) -> Event<'a> {
+  if assets.is_empty() {
+    return event;
+  }
+
+  let resolve = |url: CowStr<'a>| match assets.resolve(dir, &url) {
+    Some(asset_url) => asset_url.into(),
+    None => url,
+  };
+
+  match event {
+    Event::Start(Tag::Link(ty, url, title)) => Event::Start(Tag::Link(ty, resolve(url), title)),
+    Event::End(Tag::Link(ty, url, title)) => Event::End(Tag::Link(ty, resolve(url), title)),
+    Event::Start(Tag::Image(ty, url, title)) => Event::Start(Tag::Image(ty, resolve(url), title)),
+    Event::End(Tag::Image(ty, url, title)) => Event::End(Tag::Image(ty, resolve(url), title)),
+    event => event,
   }
 }
 
-impl Default for News {
+/// Files of a weekly news, as read from a [`NewsSource`], before being parsed.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum NewsFiles {
+  /// Content of a single Markdown file.
+  File(String),
+
+  /// Markdown files of a directory in the order they are glued, as `(path, content)`, and paths of its assets.
+  ///
+  /// Paths are relative to the directory of the news, e.g. `plugins/00-new.md`.
+  Dir {
+    mds: Vec<(String, String)>,
+    assets: Vec<String>,
+  },
+}
+
+/// Options used when parsing weekly news from Markdown.
+#[derive(Clone, Debug, Hash)]
+pub struct ParseOptions {
+  /// Shorthands turned into links, such as `#12345` for Neovim pull requests and issues.
+  pub autolinks: Autolinks,
+
+  /// Highlighter of fenced code blocks, if code must be highlighted.
+  pub highlighter: Option<Highlighter>,
+}
+
+impl Default for ParseOptions {
   fn default() -> Self {
     Self {
-      html: String::new(),
-      prev: None,
-      next: None,
+      autolinks: Autolinks::default(),
+      highlighter: Some(Highlighter::default()),
     }
   }
 }
 
+/// Metadata of a weekly news, read from its optional front matter.
+///
+/// Every field is optional; routes fall back to values computed from the [`NewsKey`] when something is missing.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct NewsMetadata {
+  /// Title of the news.
+  pub title: Option<String>,
+
+  /// Issue number.
+  pub number: Option<u32>,
+
+  /// People who wrote the news.
+  pub authors: Vec<String>,
+
+  /// People who edited the news.
+  pub editors: Vec<String>,
+
+  /// Short summary of the news.
+  pub summary: Option<String>,
+
+  /// Free-form tags.
+  pub tags: Vec<String>,
+
+  /// Date when the news is published.
+  pub date: Option<NaiveDate>,
+
+  /// Whether the news is a draft; drafts are never published.
+  pub draft: bool,
+
+  /// Instant from which the news is published, with a timezone, as a string, e.g. `"2022-07-04T09:00:00+02:00"`.
+  ///
+  /// If not set, the news is published as soon as it is loaded.
+  pub publish_at: Option<DateTime<FixedOffset>>,
+}
+
+impl NewsMetadata {
+  /// Merge metadata read from another file of the same news.
+  ///
+  /// Fields already set are kept, and lists are extended.
+  pub fn merge(&mut self, other: NewsMetadata) {
+    self.title = self.title.take().or(other.title);
+    self.number = self.number.or(other.number);
+    self.authors.extend(other.authors);
+    self.editors.extend(other.editors);
+    self.summary = self.summary.take().or(other.summary);
+    self.tags.extend(other.tags);
+    self.date = self.date.or(other.date);
+    self.draft = self.draft || other.draft;
+    self.publish_at = self.publish_at.or(other.publish_at);
+  }
+
+  /// Whether the news is published at a given instant.
+  pub fn is_published(&self, now: DateTime<Utc>) -> bool {
+    !self.draft && self.publish_at.map_or(true, |publish_at| publish_at <= now)
+  }
+
+  fn from_toml(source: &str) -> Result<Self, NewsError> {
+    let mut value: toml::Value =
+      toml::from_str(source).map_err(|e| NewsError::CannotParseFrontMatter(e.to_string()))?;
+
+    // TOML has native dates, which are not deserialized as strings; convert them so that they can be parsed as such
+    if let toml::Value::Table(ref mut table) = value {
+      for (_, value) in table.iter_mut() {
+        if let toml::Value::Datetime(datetime) = value {
+          *value = toml::Value::String(datetime.to_string());
+        }
+      }
+    }
+
+    value
+      .try_into()
+      .map_err(|e| NewsError::CannotParseFrontMatter(e.to_string()))
+  }
+
+  fn from_yaml(source: &str) -> Result<Self, NewsError> {
+    // an empty YAML document is null, not an empty mapping
+    if source.trim().is_empty() {
+      return Ok(Self::default());
+    }
+
+    serde_yaml::from_str(source).map_err(|e| NewsError::CannotParseFrontMatter(e.to_string()))
+  }
+}
+
+/// Split the optional front matter of a Markdown document from its content.
+///
+/// A document starting with a `---` line that is never closed, or closed by a line that doesn’t delimit a YAML mapping,
+/// has no front matter: those lines are thematic breaks.
+pub(crate) fn split_front_matter(md: &str) -> Result<(NewsMetadata, &str), NewsError> {
+  let delimiter = match md.lines().next().map(str::trim_end) {
+    Some(delimiter @ ("+++" | "---")) => delimiter,
+    _ => return Ok((NewsMetadata::default(), md)),
+  };
+
+  // offset of the front matter content, right after the opening delimiter line
+  let start = md.find('\n').map_or(md.len(), |i| i + 1);
+  let mut offset = start;
+
+  for line in md[start..].split_inclusive('\n') {
+    if line.trim_end() == delimiter {
+      let front_matter = &md[start..offset];
+      let content = &md[offset + line.len()..];
+
+      if delimiter == "---" && !is_yaml_front_matter(front_matter) {
+        return Ok((NewsMetadata::default(), md));
+      }
+
+      let metadata = if delimiter == "+++" {
+        NewsMetadata::from_toml(front_matter)?
+      } else {
+        NewsMetadata::from_yaml(front_matter)?
+      };
+
+      return Ok((metadata, content));
+    }
+
+    offset += line.len();
+  }
+
+  // without a closing delimiter, a leading `---` is a thematic break
+  if delimiter == "---" {
+    return Ok((NewsMetadata::default(), md));
+  }
+
+  Err(NewsError::CannotParseFrontMatter(format!(
+    "missing closing {} delimiter",
+    delimiter
+  )))
+}
+
+/// Whether the text between two `---` lines is a YAML front matter rather than Markdown content.
+///
+/// That is the case if it is empty or a mapping. Invalid YAML is still a front matter if it has `key:` lines, so that
+/// mistakes in front matters are reported.
+fn is_yaml_front_matter(source: &str) -> bool {
+  if source.trim().is_empty() {
+    return true;
+  }
+
+  match serde_yaml::from_str(source) {
+    Ok(serde_yaml::Value::Mapping(_)) => true,
+    Ok(_) => false,
+    Err(_) => source.lines().any(|line| {
+      line.split_once(':').is_some_and(|(key, value)| {
+        !key.is_empty()
+          && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+          && (value.is_empty() || value.starts_with(char::is_whitespace))
+      })
+    }),
+  }
+}
+
 /// Key used to uniquely refer to a weekly news.
 ///
 /// It is composed of the year and week number.
@@ -144,9 +513,28 @@ impl NewsKey {
       day = self.day
     ))
   }
+
+  /// URL path under which the assets of the news are served.
+  pub fn assets_url(&self) -> String {
+    format!("/{}/{}/{:02}/assets", self.year, self.month, self.day)
+  }
+
+  /// Date of the news, if the key represents a valid date.
+  pub fn to_date(&self) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(self.year.into(), self.month.number(), self.day.into())
+  }
+
+  /// Key of the news published at a given date.
+  pub fn from_date(date: NaiveDate) -> Option<Self> {
+    Some(Self {
+      year: date.year().try_into().ok()?,
+      month: Month::from_number(date.month())?,
+      day: date.day() as u8,
+    })
+  }
 }
 
-fn file_name_to_day(name: &str) -> Result<u8, NewsError> {
+pub(crate) fn file_name_to_day(name: &str) -> Result<u8, NewsError> {
   // the format is NN.md, so the len() must always be 5
   if name.len() != 5 {
     return Err(NewsError::CannotParseDay(name.to_owned()));
@@ -158,7 +546,7 @@ fn file_name_to_day(name: &str) -> Result<u8, NewsError> {
     .map_err(|_| NewsError::CannotParseDay(name.to_owned()))
 }
 
-fn dir_name_to_day(name: &str) -> Result<u8, NewsError> {
+pub(crate) fn dir_name_to_day(name: &str) -> Result<u8, NewsError> {
   // the format is NN, so the len() must always be 2
   if name.len() != 2 {
     return Err(NewsError::CannotParseDay(name.to_owned()));
@@ -169,209 +557,966 @@ fn dir_name_to_day(name: &str) -> Result<u8, NewsError> {
     .map_err(|_| NewsError::CannotParseDay(name.to_owned()))
 }
 
+/// Find the key of the news a path belongs to, relatively to the root directory.
+fn key_from_relative_path(root_path: &Path, path: &Path) -> Option<NewsKey> {
+  // the watcher reports absolute paths, so we might have to make the root path absolute as well
+  let relative_path = path.strip_prefix(root_path).ok().or_else(|| {
+    let root_path = env::current_dir().ok()?.join(root_path);
+    path.strip_prefix(root_path).ok()
+  })?;
+
+  let mut components = relative_path
+    .components()
+    .map(|component| component.as_os_str().to_str());
+  let year = components.next()??.parse().ok()?;
+  let month = components.next()??.parse().ok()?;
+  let day_name = components.next()??;
+
+  let day = if components.next().is_some() {
+    dir_name_to_day(day_name)
+  } else {
+    file_name_to_day(day_name).or_else(|_| dir_name_to_day(day_name))
+  }
+  .ok()?;
+
+  Some(NewsKey { year, month, day })
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LatestNews {
   pub key: NewsKey,
   pub news: News,
 }
 
-#[derive(Debug)]
+/// How to react to errors while loading weekly news.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LoadMode {
+  /// Stop at the first error.
+  #[default]
+  Strict,
+
+  /// Skip the entries that cannot be loaded and record them in a [`LoadReport`].
+  Lenient,
+}
+
+/// An entry that couldn’t be loaded.
+#[derive(Clone, Debug)]
+pub struct LoadFailure {
+  /// Full path of the entry.
+  pub path: PathBuf,
+
+  pub error: NewsError,
+}
+
+/// Failures collected while loading weekly news in [`LoadMode::Lenient`].
+#[derive(Clone, Debug, Default)]
+pub struct LoadReport {
+  pub failures: Vec<LoadFailure>,
+}
+
+impl LoadReport {
+  pub fn is_empty(&self) -> bool {
+    self.failures.is_empty()
+  }
+}
+
+/// All the weekly news, indexed by their keys.
+///
+/// A store is not meant to be shared while being mutated: see [`NewsState`] for publishing generations of stores to
+/// concurrent readers.
+#[derive(Clone, Debug)]
 pub struct NewsStore {
   root_path: PathBuf,
-  news: HashMap<NewsKey, News>,
+
+  // news and indexes are shared between generations (see NewsState), and only copied when they change
+  news: BTreeMap<NewsKey, Arc<News>>,
+
+  // drafts and news scheduled for later; they are not linked to any other news, nor indexed
+  unpublished: BTreeMap<NewsKey, Arc<News>>,
+
+  numbers: IssueNumbers,
+  load_mode: LoadMode,
+  report: LoadReport,
+  search_index: Arc<SearchIndex>,
+  plugin_index: Arc<PluginIndex>,
+  parse_options: ParseOptions,
+
+  // where news are read from, and the state of the source the store was last populated from, if it was pinned
+  source: Arc<dyn NewsSource>,
+  pinned_source: Option<Arc<dyn NewsSource>>,
+
+  // hashes of the files each news (published or not) was parsed from, to avoid parsing unchanged news again
+  hashes: BTreeMap<NewsKey, u64>,
+  snapshot_path: Option<PathBuf>,
 }
 
 impl NewsStore {
   /// Create a new empty news store.
   pub fn new(root_path: impl Into<PathBuf>) -> Self {
+    let root_path: PathBuf = root_path.into();
+    let news = BTreeMap::new();
+    let numbers = IssueNumbers::default();
+    Self {
+      source: Arc::new(FsSource::new(&root_path)),
+      root_path,
+      news,
+      unpublished: BTreeMap::new(),
+      numbers,
+      load_mode: LoadMode::default(),
+      report: LoadReport::default(),
+      search_index: Arc::default(),
+      plugin_index: Arc::default(),
+      parse_options: ParseOptions::default(),
+      pinned_source: None,
+      hashes: BTreeMap::new(),
+      snapshot_path: None,
+    }
+  }
+
+  /// Create a new empty news store reading from a root that is either a directory or a tar archive of one.
+  ///
+  /// Archives are read with a [`TarSource`]; news are then reported with paths inside the archive path, e.g.
+  /// `contents.tar/2022/Jun/06.md`.
+  pub fn open(root_path: impl Into<PathBuf>) -> Result<Self, NewsError> {
     let root_path = root_path.into();
-    let news = HashMap::new();
-    Self { root_path, news }
+
+    if root_path.is_file() {
+      let source = TarSource::open(&root_path)?;
+      Ok(Self::new(root_path).with_source(source))
+    } else {
+      Ok(Self::new(root_path))
+    }
+  }
+
+  /// Change how errors are handled while loading weekly news.
+  pub fn with_load_mode(mut self, load_mode: LoadMode) -> Self {
+    self.load_mode = load_mode;
+    self
+  }
+
+  /// Use already assigned (and possibly persisted) issue numbers.
+  pub fn with_numbers(mut self, numbers: IssueNumbers) -> Self {
+    self.numbers = numbers;
+    self
+  }
+
+  /// Change how weekly news are parsed.
+  pub fn with_parse_options(mut self, parse_options: ParseOptions) -> Self {
+    self.parse_options = parse_options;
+    self
+  }
+
+  /// Read news from another source than the root directory, such as a git revision or a tar archive.
+  ///
+  /// The root path is still used to report failures and to find the news paths belong to.
+  pub fn with_source(self, source: impl NewsSource + 'static) -> Self {
+    self.with_shared_source(Arc::new(source))
+  }
+
+  fn with_shared_source(mut self, source: Arc<dyn NewsSource>) -> Self {
+    self.source = source;
+    self
+  }
+
+  /// Persist a snapshot of the parsed news at the given path (see [`NewsStore::save_snapshot`]).
+  ///
+  /// See [`NewsStore::restore_snapshot`] to serve them right away when starting.
+  pub fn with_snapshot(mut self, path: impl Into<PathBuf>) -> Self {
+    self.snapshot_path = Some(path.into());
+    self
   }
 
-  /// Get all the keys
-  pub fn keys<'a>(&'a self) -> impl Iterator<Item = &'a NewsKey> {
+  /// Source news are currently read from: the pinned state of the source, if any, or the source itself.
+  fn current_source(&self) -> Arc<dyn NewsSource> {
+    self.pinned_source.as_ref().unwrap_or(&self.source).clone()
+  }
+
+  /// Version of the contents the store was last populated from, for sources having versions.
+  ///
+  /// For instance, it is the commit news were read from when reading from a git revision.
+  pub fn source_version(&self) -> Option<String> {
+    self.pinned_source.as_ref()?.version()
+  }
+
+  /// Create a new store populated by scanning the root directory.
+  ///
+  /// See [`NewsStore::populate_from_root`] for further details.
+  pub fn load_from_root(root_path: impl Into<PathBuf>) -> Result<Self, NewsError> {
+    let mut store = Self::new(root_path);
+    store.populate_from_root()?;
+    Ok(store)
+  }
+
+  /// Get all the keys, sorted from the oldest to the most recent news.
+  pub fn keys(&self) -> impl DoubleEndedIterator<Item = &NewsKey> {
     self.news.keys()
   }
 
   /// Get a news from a key, if exists.
+  ///
+  /// Only published news are considered; see [`NewsStore::get_unpublished`] for the others.
   pub fn get(&self, key: &NewsKey) -> Option<&News> {
-    self.news.get(key)
+    self.news.get(key).map(Arc::as_ref)
+  }
+
+  /// Get all the keys of the news that are not published yet (drafts and scheduled news), sorted.
+  pub fn unpublished_keys(&self) -> impl DoubleEndedIterator<Item = &NewsKey> {
+    self.unpublished.keys()
+  }
+
+  /// Get a news that is not published yet, if exists.
+  pub fn get_unpublished(&self, key: &NewsKey) -> Option<&News> {
+    self.unpublished.get(key).map(Arc::as_ref)
+  }
+
+  /// Get the earliest instant at which a scheduled news is to be published, if any.
+  pub fn next_publication(&self) -> Option<DateTime<Utc>> {
+    self
+      .unpublished
+      .values()
+      .filter(|news| !news.metadata.draft)
+      .filter_map(|news| news.metadata.publish_at)
+      .map(|publish_at| publish_at.with_timezone(&Utc))
+      .min()
+  }
+
+  /// Publish the scheduled news whose publication instant has passed, returning their keys.
+  pub fn publish_due(&mut self, now: DateTime<Utc>) -> Vec<NewsKey> {
+    let due: Vec<_> = self
+      .unpublished
+      .iter()
+      .filter(|(_, news)| news.metadata.is_published(now))
+      .map(|(key, _)| *key)
+      .collect();
+
+    for key in &due {
+      if let Some(news) = self.unpublished.remove(key) {
+        log::info!("publishing news {:?}", key);
+        self.news.insert(*key, news);
+        self.link_and_index(*key);
+      }
+    }
+
+    if !due.is_empty() {
+      self.assign_numbers();
+    }
+
+    due
   }
 
-  /// Populate the store by scanning the root directory adding all of its content.
+  /// Get the issue number of a news, if exists.
   ///
-  /// We currently support two ways of reading news:
+  /// The number set in the metadata of the news has precedence over the assigned one.
+  pub fn number(&self, key: &NewsKey) -> Option<u32> {
+    let news = self.news.get(key)?;
+    news.metadata.number.or_else(|| self.numbers.get(key))
+  }
+
+  /// Get the key of the news with the given issue number, if exists.
+  pub fn key_from_number(&self, number: u32) -> Option<NewsKey> {
+    self
+      .news
+      .keys()
+      .find(|key| self.number(key) == Some(number))
+      .copied()
+  }
+
+  /// Get the failures collected while loading in [`LoadMode::Lenient`].
+  pub fn report(&self) -> &LoadReport {
+    &self.report
+  }
+
+  /// Get the full-text search index of the news.
+  pub fn search_index(&self) -> &SearchIndex {
+    &self.search_index
+  }
+
+  /// Get the index of the repositories mentioned in the news.
+  pub fn plugin_index(&self) -> &PluginIndex {
+    &self.plugin_index
+  }
+
+  /// Read the content of an asset of a published news from the source of the store, if it exists.
   ///
-  /// - Encoded as Markdown in a single file, e.g. 12.md, where the number is the day.
-  /// - The news is split into sub-directories in a directory, e.g. 12/…, where the number is the day.
-  pub fn populate_from_root(&mut self) -> Result<(), NewsError> {
-    for entry in fs::read_dir(&self.root_path)? {
-      if let Ok(entry) = entry {
-        self.traverse_year(entry)?;
-      }
+  /// `asset` is the path of the asset relative to the directory of the news, as found in [`News::assets`].
+  pub fn read_asset(&self, key: &NewsKey, asset: &str) -> Option<Vec<u8>> {
+    self.read_asset_of(key, self.news.get(key)?, asset)
+  }
+
+  /// Same as [`NewsStore::read_asset`], for news that are not published yet.
+  pub fn read_unpublished_asset(&self, key: &NewsKey, asset: &str) -> Option<Vec<u8>> {
+    self.read_asset_of(key, self.unpublished.get(key)?, asset)
+  }
+
+  fn read_asset_of(&self, key: &NewsKey, news: &News, asset: &str) -> Option<Vec<u8>> {
+    if news.assets.contains(asset) {
+      self.current_source().read_asset(*key, asset)
+    } else {
+      None
     }
+  }
 
-    self.update_prev_next();
+  /// Get the issue numbers assigned by this store.
+  pub fn numbers(&self) -> &IssueNumbers {
+    &self.numbers
+  }
+
+  /// Persist the issue numbers assigned by this store, if they were loaded from a file.
+  ///
+  /// [`NewsState`] does it every time it publishes a generation with new numbers.
+  pub fn save_numbers(&self) -> Result<(), NewsError> {
+    self.numbers.save()
+  }
+
+  /// Get the keys of the news right before and right after `key`, if any.
+  ///
+  /// `key` doesn’t have to be present in the store.
+  pub fn neighbours(&self, key: &NewsKey) -> (Option<NewsKey>, Option<NewsKey>) {
+    let prev = self.news.range(..key).next_back().map(|(k, _)| *k);
+    let next = self
+      .news
+      .range((Bound::Excluded(key), Bound::Unbounded))
+      .next()
+      .map(|(k, _)| *k);
+
+    (prev, next)
+  }
+
+  /// Find the key of the news a path (file or directory) belongs to, if any.
+  ///
+  /// The path must live in the root directory and follow one of the supported layouts; i.e. `YYYY/Mon/DD.md` or
+  /// `YYYY/Mon/DD/…`.
+  pub fn key_from_path(&self, path: impl AsRef<Path>) -> Option<NewsKey> {
+    key_from_relative_path(&self.root_path, path.as_ref())
+  }
+
+  /// Update (or create) a single weekly news by reading it again from its source.
+  ///
+  /// Only the news associated with `key` is parsed; the prev / next keys of it and its neighbours are fixed
+  /// accordingly.
+  ///
+  /// In [`LoadMode::Lenient`], if the news cannot be loaded, the failure is recorded and the previous version of the
+  /// news, if any, is kept.
+  ///
+  /// If the news is not published yet, it is hidden until [`NewsStore::publish_due`] publishes it.
+  pub fn update(&mut self, key: NewsKey) -> Result<(), NewsError> {
+    self.forget_failures(key);
+
+    let files = self.read(key).and_then(|files| {
+      files.ok_or_else(|| LoadFailure {
+        path: key.to_file_path(&self.root_path),
+        error: io::Error::from(io::ErrorKind::NotFound).into(),
+      })
+    });
+
+    self.update_with(key, files)
+  }
+
+  /// Update (or create) a weekly news from its files, once read.
+  fn update_with(
+    &mut self,
+    key: NewsKey,
+    files: Result<NewsFiles, LoadFailure>,
+  ) -> Result<(), NewsError> {
+    let result = files.and_then(|files| self.load(key, files));
+
+    if let Err(failure) = result {
+      self.fail(failure)?;
+      self.assign_numbers();
+      return Ok(());
+    }
+
+    let published = self
+      .news
+      .get(&key)
+      .map_or(true, |news| news.metadata.is_published(Utc::now()));
+
+    if !published {
+      // unlink the news from its neighbours, in case it was published before
+      if let Some(news) = self.remove(key) {
+        self.unpublished.insert(key, news);
+      }
+    } else {
+      self.unpublished.remove(&key);
+      self.link_and_index(key);
+      self.assign_numbers();
+    }
 
     Ok(())
   }
 
-  fn traverse_year(&mut self, entry: DirEntry) -> Result<(), NewsError> {
-    log::debug!("traversing year {}", entry.path().display());
+  /// Link a published news to its neighbours and index it.
+  fn link_and_index(&mut self, key: NewsKey) {
+    if let Some(news) = self.news.get(&key) {
+      Arc::make_mut(&mut self.search_index).insert(key, news);
+      Arc::make_mut(&mut self.plugin_index).insert(key, news);
+    }
 
-    if entry.path().is_dir() {
-      let year = entry
-        .file_name()
-        .to_str()
-        .and_then(|name| name.parse().ok())
-        .ok_or_else(|| NewsError::CannotParseYear(format!("{:?}", entry.file_name())))?;
+    self.update_prev_next_around(key);
+  }
 
-      for month_entry in fs::read_dir(entry.path())? {
-        if let Ok(month_entry) = month_entry {
-          self.traverse_month(month_entry, year)?;
-        }
+  /// Remove a weekly news from the store, returning it if it was present.
+  ///
+  /// The neighbours of the removed news are linked to each other.
+  pub fn remove(&mut self, key: NewsKey) -> Option<Arc<News>> {
+    let unpublished = self.unpublished.remove(&key);
+    let news = match self.news.remove(&key) {
+      Some(news) => news,
+      None => return unpublished,
+    };
+    let (prev, next) = self.neighbours(&key);
+    Arc::make_mut(&mut self.search_index).remove(key);
+    Arc::make_mut(&mut self.plugin_index).remove(key);
+    self.link(prev, next);
+
+    Some(news)
+  }
+
+  /// Move a weekly news from a key to another one, e.g. when its file or directory was renamed.
+  ///
+  /// Both keys are synchronized with the source, so that partial moves (a single file of a news split into
+  /// sub-directories) are correctly handled.
+  pub fn rename(&mut self, from: NewsKey, to: NewsKey) -> Result<(), NewsError> {
+    self.refresh(from)?;
+    self.refresh(to)
+  }
+
+  /// Read the Markdown files a weekly news is made of from the source of the store, in the order they are glued.
+  ///
+  /// Files are returned as `(path, content)`, with their paths in the root path. Assets are not listed.
+  pub fn files(&self, key: NewsKey) -> Result<Vec<(PathBuf, String)>, NewsError> {
+    let files = match self.read(key).map_err(|failure| failure.error)? {
+      Some(NewsFiles::File(md)) => vec![(key.to_file_path(&self.root_path), md)],
+
+      Some(NewsFiles::Dir { mds, .. }) => {
+        let dir_path = key.to_dir_path(&self.root_path);
+        mds
+          .into_iter()
+          .map(|(path, md)| (dir_path.join(path), md))
+          .collect()
       }
+
+      None => Vec::new(),
+    };
+
+    Ok(files)
+  }
+
+  /// Parse a weekly news of the store again, with its assets served under another URL than [`NewsKey::assets_url`],
+  /// such as the preview URL of news that are not published yet.
+  ///
+  /// The news is read again from the source of the store, published or not; it keeps its neighbours and the date of its
+  /// last change. News without assets are returned as is.
+  pub fn reparse_with_assets_url(
+    &self,
+    key: NewsKey,
+    assets_url: impl Into<String>,
+  ) -> Result<Option<News>, NewsError> {
+    let stored = match self.unpublished.get(&key).or_else(|| self.news.get(&key)) {
+      Some(stored) => stored,
+      None => return Ok(None),
+    };
+
+    if stored.assets.is_empty() {
+      return Ok(Some(News::clone(stored)));
     }
 
+    let files = match self.read(key).map_err(|failure| failure.error)? {
+      Some(files) => files,
+      None => return Ok(None),
+    };
+    let mut news = self
+      .parse_files_with_assets_url(key, files, assets_url.into())
+      .map_err(|failure| failure.error)?;
+    news.last_updated = stored.last_updated;
+    news.prev = stored.prev;
+    news.next = stored.next;
+
+    Ok(Some(news))
+  }
+
+  /// Synchronize a weekly news with its source.
+  ///
+  /// If the news still exists, it is updated (or created); otherwise, it is removed from the store.
+  pub fn refresh(&mut self, key: NewsKey) -> Result<(), NewsError> {
+    self.forget_failures(key);
+
+    match self.read(key) {
+      Ok(Some(files)) => self.update_with(key, Ok(files)),
+      Err(failure) => self.update_with(key, Err(failure)),
+
+      Ok(None) => {
+        log::debug!("removing news key: {:?}", key);
+        let _ = self.remove(key);
+        self.hashes.remove(&key);
+        Ok(())
+      }
+    }
+  }
+
+  /// Read the files of a weekly news from the current source.
+  fn read(&self, key: NewsKey) -> Result<Option<NewsFiles>, LoadFailure> {
+    self
+      .current_source()
+      .read(key)
+      .map_err(|failure| self.rooted(failure))
+  }
+
+  /// Report a failure of a source in the root path.
+  fn rooted(&self, failure: LoadFailure) -> LoadFailure {
+    LoadFailure {
+      path: self.root_path.join(failure.path),
+      error: failure.error,
+    }
+  }
+
+  /// Parse the files of a weekly news and insert it, along with the date of its last change if known.
+  fn load(&mut self, key: NewsKey, files: NewsFiles) -> Result<(), LoadFailure> {
+    let previous = self
+      .hashes
+      .get(&key)
+      .copied()
+      .zip(self.news.get(&key).or_else(|| self.unpublished.get(&key)));
+    let (mut news, hash) = self.parse_changed(key, files, previous)?;
+    self.hashes.insert(key, hash);
+    let last_updated = self
+      .current_source()
+      .last_updated(&[key])
+      .map_err(|error| LoadFailure {
+        path: key.to_dir_path(&self.root_path),
+        error,
+      })?
+      .remove(&key);
+    set_last_updated(&mut news, last_updated);
+    let _ = self.news.insert(key, news);
+
     Ok(())
   }
 
-  fn traverse_month(&mut self, entry: DirEntry, year: u16) -> Result<(), NewsError> {
-    log::debug!("traversing month {}", entry.path().display());
+  /// Populate the store by reading all the news of its source; by default, the root directory.
+  ///
+  /// We currently support two ways of reading news:
+  ///
+  /// - Encoded as Markdown in a single file, e.g. 12.md, where the number is the day.
+  /// - The news is split into sub-directories in a directory, e.g. 12/…, where the number is the day.
+  ///
+  /// News that are not published yet are hidden until [`NewsStore::publish_due`] publishes them.
+  ///
+  /// The source is pinned first (see [`NewsSource::pin`]), so that a source following something moving, such as a git
+  /// branch, is read in a single state.
+  ///
+  /// All the files are read before being parsed in parallel; news are then linked to each other once they are all
+  /// parsed. News already parsed by the store (e.g. restored from a snapshot) are parsed again only if their files
+  /// changed.
+  ///
+  /// In [`LoadMode::Lenient`], news that cannot be loaded are recorded as failures and keep their previous version, if
+  /// any.
+  pub fn populate_from_root(&mut self) -> Result<(), NewsError> {
+    let mut parsed = mem::take(&mut self.news);
+    parsed.append(&mut self.unpublished);
+    let hashes = mem::take(&mut self.hashes);
+    self.report = LoadReport::default();
+    self.pinned_source = self.source.pin()?;
 
-    if entry.path().is_dir() {
-      let month: Month = entry
-        .file_name()
-        .to_str()
-        .and_then(|name| name.parse().ok())
-        .ok_or_else(|| NewsError::CannotParseMonth(format!("{:?}", entry.file_name())))?;
+    let source = self.current_source();
 
-      for day_entry in fs::read_dir(entry.path())? {
-        if let Ok(day_entry) = day_entry {
-          self.traverse_day(day_entry, year, month)?;
+    // read everything first, in the order of the source; only parsing, the expensive part, is done in parallel
+    let mut seen = BTreeSet::new();
+    let mut all_files = Vec::new();
+
+    for entry in source.list()? {
+      let key = match entry {
+        Ok(key) => key,
+        Err(failure) => {
+          let failure = self.rooted(failure);
+          self.fail(failure)?;
+          continue;
         }
+      };
+
+      // a news can be found twice, as a single file and as a directory; the directory wins, as in update
+      if !seen.insert(key) {
+        continue;
+      }
+
+      let files = self.read(key).and_then(|files| {
+        files.ok_or_else(|| LoadFailure {
+          path: key.to_file_path(&self.root_path),
+          error: io::Error::from(io::ErrorKind::NotFound).into(),
+        })
+      });
+
+      match files {
+        Ok(files) => all_files.push((key, files)),
+        Err(failure) => self.fail_keeping_previous(key, failure, &mut parsed, &hashes)?,
       }
     }
 
+    // results are collected in the order of the source, so that the store doesn’t depend on scheduling
+    let results: Vec<_> = all_files
+      .into_par_iter()
+      .map(|(key, files)| {
+        let previous = hashes.get(&key).copied().zip(parsed.get(&key));
+        (key, self.parse_changed(key, files, previous))
+      })
+      .collect();
+
+    for (key, result) in results {
+      match result {
+        Ok((news, hash)) => {
+          self.news.insert(key, news);
+          self.hashes.insert(key, hash);
+        }
+
+        Err(failure) => self.fail_keeping_previous(key, failure, &mut parsed, &hashes)?,
+      }
+    }
+
+    let keys: Vec<_> = self.news.keys().copied().collect();
+    let mut dates = source.last_updated(&keys)?;
+    for (key, news) in &mut self.news {
+      set_last_updated(news, dates.remove(key));
+    }
+
+    self.finish_population();
+
     Ok(())
   }
 
-  fn traverse_day(&mut self, entry: DirEntry, year: u16, month: Month) -> Result<(), NewsError> {
-    log::debug!("found day {}", entry.path().display());
+  /// Populate the store with the news of the snapshot, if any, without reading the source.
+  ///
+  /// The snapshot is ignored if it was made by another version of twin or with other parse options. Return whether it
+  /// was restored.
+  ///
+  /// The restored news might be outdated: [`NewsStore::populate_from_root`] must still be called to synchronize the
+  /// store with its source, which parses again only the news that changed.
+  pub fn restore_snapshot(&mut self) -> Result<bool, NewsError> {
+    let path = match self.snapshot_path {
+      Some(ref path) => path,
+      None => return Ok(false),
+    };
+
+    let snapshot = match Snapshot::load(path)? {
+      Some(snapshot) => snapshot,
+      None => return Ok(false),
+    };
 
-    if entry.path().is_file() {
-      let day: u8 = entry
-        .file_name()
-        .to_str()
-        .ok_or_else(|| NewsError::CannotParseDay(format!("{:?}", entry.file_name())))
-        .and_then(file_name_to_day)?;
+    if snapshot.fingerprint != snapshot::fingerprint(&self.parse_options) {
+      log::info!("ignoring outdated snapshot {}", path.display());
+      return Ok(false);
+    }
 
-      let key = NewsKey { year, month, day };
-      self.update_from_file_path(key)?;
-    } else if entry.path().is_dir() {
-      let day = entry
-        .file_name()
-        .to_str()
-        .ok_or_else(|| NewsError::CannotParseDay(format!("{:?}", entry.file_name())))
-        .and_then(dir_name_to_day)?;
+    self.news.clear();
+    self.unpublished.clear();
+    self.hashes.clear();
 
-      let key = NewsKey { year, month, day };
-      self.update_from_dir_path(key, entry)?;
+    for entry in snapshot.news {
+      self.news.insert(entry.key, entry.news);
+      self.hashes.insert(entry.key, entry.hash);
     }
 
-    Ok(())
+    self.finish_population();
+
+    Ok(true)
   }
 
-  /// Update (or create) a weekly news by reading the Markdown news and converting it to HTML.
+  /// Hide the news that are not published yet, link and index the other ones once they are all inserted.
+  fn finish_population(&mut self) {
+    let now = Utc::now();
+    let (news, unpublished) = mem::take(&mut self.news)
+      .into_iter()
+      .partition(|(_, news)| news.metadata.is_published(now));
+    self.news = news;
+    self.unpublished = unpublished;
+
+    self.update_prev_next();
+    self.assign_numbers();
+    let news = || self.news.iter().map(|(key, news)| (key, news.as_ref()));
+    self.search_index = Arc::new(SearchIndex::from_news(news()));
+    self.plugin_index = Arc::new(PluginIndex::from_news(news()));
+  }
+
+  /// Persist the parsed news, if a snapshot path was provided.
   ///
-  /// If some HTML was already present for that key, it is returned.
-  fn update_from_file_path(&mut self, key: NewsKey) -> Result<(), NewsError> {
-    let path = key.to_file_path(&self.root_path);
+  /// [`NewsState`] does it every time it publishes a generation whose news changed.
+  pub fn save_snapshot(&self) -> Result<(), NewsError> {
+    let path = match self.snapshot_path {
+      Some(ref path) => path,
+      None => return Ok(()),
+    };
 
-    log::debug!("updating news key: {:?} (path={})", key, path.display());
+    let news = self
+      .news
+      .iter()
+      .chain(&self.unpublished)
+      .filter_map(|(key, news)| {
+        let hash = *self.hashes.get(key)?;
+        Some(SnapshotEntry {
+          key: *key,
+          hash,
+          news: news.clone(),
+        })
+      })
+      .collect();
+    let snapshot = Snapshot::new(snapshot::fingerprint(&self.parse_options), news);
+    snapshot.save(path)
+  }
 
-    let news = News::load_from_md(path)?;
-    let _ = self.news.insert(key, news);
+  /// Assign numbers to new news, including the ones that failed to load, unless they were hidden.
+  ///
+  /// Numbers are not persisted here, as the store might be a copy that is never published; see
+  /// [`NewsStore::save_numbers`].
+  fn assign_numbers(&mut self) {
+    let root_path = &self.root_path;
+    let failed: BTreeSet<_> = self
+      .report
+      .failures
+      .iter()
+      .filter_map(|failure| key_from_relative_path(root_path, &failure.path))
+      .filter(|key| !self.unpublished.contains_key(key))
+      .collect();
 
-    Ok(())
+    self.numbers.assign(&self.news, &failed);
   }
 
-  /// Update (or creat) a weekly news by (recursively) extracting all the Markdown content from all files in the
-  /// directory and concatenating them into a single one. The directory entries are sorted by names and glued according
-  /// to a DFS of the tree.
-  fn update_from_dir_path(&mut self, key: NewsKey, dir: DirEntry) -> Result<(), NewsError> {
-    log::debug!(
-      "updating news key: {:?}, (dir={})",
-      key,
-      dir.path().display()
-    );
+  /// Remove the recorded failures of a news, e.g. before loading it again.
+  fn forget_failures(&mut self, key: NewsKey) {
+    let root_path = &self.root_path;
+    self
+      .report
+      .failures
+      .retain(|failure| key_from_relative_path(root_path, &failure.path) != Some(key));
+  }
 
-    let mut news = News::default();
-    self.update_from_subdirs(&mut news, dir)?;
-    let _ = self.news.insert(key, news);
+  /// Handle a failure according to the load mode: either record it or propagate it.
+  fn fail(&mut self, failure: LoadFailure) -> Result<(), NewsError> {
+    log::error!("cannot load {}: {}", failure.path.display(), failure.error);
 
-    Ok(())
+    match self.load_mode {
+      LoadMode::Strict => Err(failure.error),
+      LoadMode::Lenient => {
+        self.report.failures.push(failure);
+        Ok(())
+      }
+    }
   }
 
-  fn update_from_subdirs(&mut self, news: &mut News, file: DirEntry) -> Result<(), NewsError> {
-    if file.path().is_dir() {
-      let mut files = fs::read_dir(file.path())?
-        .filter_map(|entry| entry.ok())
-        .collect::<Vec<_>>();
-      files.sort_by_key(|entry| entry.path());
+  /// Handle the failure of a news while populating the store, keeping its previous version in service, if any, as
+  /// [`NewsStore::update`] does.
+  ///
+  /// `parsed` and `hashes` are the news and hashes of the store before it was populated.
+  fn fail_keeping_previous(
+    &mut self,
+    key: NewsKey,
+    failure: LoadFailure,
+    parsed: &mut BTreeMap<NewsKey, Arc<News>>,
+    hashes: &BTreeMap<NewsKey, u64>,
+  ) -> Result<(), NewsError> {
+    self.fail(failure)?;
+
+    if let Some(news) = parsed.remove(&key) {
+      self.news.insert(key, news);
 
-      for subfile in files {
-        self.update_from_subdirs(news, subfile)?;
+      if let Some(hash) = hashes.get(&key) {
+        self.hashes.insert(key, *hash);
       }
-    } else {
-      let temporary_news = News::load_from_md(file.path())?;
-      news.html += "\n";
-      news.html += &temporary_news.html;
     }
 
     Ok(())
   }
 
+  /// Parse the files of a weekly news, unless they didn’t change since the previous version of the news was parsed.
+  ///
+  /// `previous` is the hash of the files of the previous version, along with the version itself, which is shared
+  /// rather than copied if the files didn’t change. The hash of the files is returned along with the news.
+  fn parse_changed(
+    &self,
+    key: NewsKey,
+    files: NewsFiles,
+    previous: Option<(u64, &Arc<News>)>,
+  ) -> Result<(Arc<News>, u64), LoadFailure> {
+    let hash = snapshot::hash_files(&files);
+
+    if let Some((previous_hash, news)) = previous {
+      if previous_hash == hash {
+        log::debug!("news {:?} didn’t change; not parsing it again", key);
+        return Ok((news.clone(), hash));
+      }
+    }
+
+    self
+      .parse_files(key, files)
+      .map(|news| (Arc::new(news), hash))
+  }
+
+  /// Parse the files of a weekly news, as read from a source.
+  fn parse_files(&self, key: NewsKey, files: NewsFiles) -> Result<News, LoadFailure> {
+    self.parse_files_with_assets_url(key, files, key.assets_url())
+  }
+
+  /// Same as [`NewsStore::parse_files`], with the assets served under `assets_url`.
+  fn parse_files_with_assets_url(
+    &self,
+    key: NewsKey,
+    files: NewsFiles,
+    assets_url: String,
+  ) -> Result<News, LoadFailure> {
+    match files {
+      NewsFiles::File(md) => {
+        News::parse_from_md_with(md, &self.parse_options).map_err(|error| LoadFailure {
+          path: key.to_file_path(&self.root_path),
+          error,
+        })
+      }
+
+      NewsFiles::Dir { mds, assets } => News::parse_from_md_files_with_assets(
+        mds.iter().map(|(path, md)| {
+          let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+          (dir, md.as_str())
+        }),
+        Assets::new(assets_url, assets),
+        &self.parse_options,
+      )
+      .map_err(|error| LoadFailure {
+        path: key.to_dir_path(&self.root_path),
+        error,
+      }),
+    }
+  }
+
   /// Traverse the news and set the prev / next news keys.
   pub fn update_prev_next(&mut self) {
-    if self.news.len() < 2 {
-      return;
+    let keys: Vec<_> = self.news.keys().copied().collect();
+
+    // the first news doesn’t have any previous and the last news doesn’t have any next
+    for (i, news) in self.news.values_mut().enumerate() {
+      set_neighbours(
+        news,
+        i.checked_sub(1).map(|i| keys[i]),
+        keys.get(i + 1).copied(),
+      );
     }
+  }
 
-    let mut keys: Vec<_> = self.news.iter_mut().collect();
-    let keys_len = keys.len();
-    keys.sort_by_key(|(k, _)| **k);
+  /// Set the prev / next news keys of a single news and link its neighbours back to it.
+  fn update_prev_next_around(&mut self, key: NewsKey) {
+    let (prev, next) = self.neighbours(&key);
 
-    // the first news doesn’t have any previous and the last news doesn’t have any next
-    keys[0].1.prev = None;
-    keys[0].1.next = Some(keys[1].0.clone());
-    keys[keys_len - 1].1.prev = Some(keys[keys_len - 2].0.clone());
-    keys[keys_len - 1].1.next = None;
+    if let Some(news) = self.news.get_mut(&key) {
+      set_neighbours(news, prev, next);
+    }
+
+    self.link(prev, Some(key));
+    self.link(Some(key), next);
+  }
 
-    for i in 1..keys_len - 1 {
-      let prev = keys[i - 1].0.clone();
-      let next = keys[i + 1].0.clone();
-      keys[i].1.prev = Some(prev);
-      keys[i].1.next = Some(next);
+  /// Link two consecutive news to each other, if present.
+  fn link(&mut self, prev: Option<NewsKey>, next: Option<NewsKey>) {
+    if let Some(news) = prev.and_then(|prev| self.news.get_mut(&prev)) {
+      let before = news.prev;
+      set_neighbours(news, before, next);
     }
+
+    if let Some(news) = next.and_then(|next| self.news.get_mut(&next)) {
+      let after = news.next;
+      set_neighbours(news, prev, after);
+    }
+  }
+}
+
+/// Set the neighbours of a news, copying it only if they change, as it might be shared with other generations.
+fn set_neighbours(news: &mut Arc<News>, prev: Option<NewsKey>, next: Option<NewsKey>) {
+  if news.prev != prev || news.next != next {
+    let news = Arc::make_mut(news);
+    news.prev = prev;
+    news.next = next;
+  }
+}
+
+/// Set the date of the last change of a news, copying it only if it changes.
+fn set_last_updated(news: &mut Arc<News>, last_updated: Option<DateTime<Utc>>) {
+  if news.last_updated != last_updated {
+    Arc::make_mut(news).last_updated = last_updated;
   }
 }
 
 /// Sharable news.
+///
+/// The state holds the current generation of a [`NewsStore`]. A generation is never mutated once published: updates
+/// are applied on a copy that replaces the current generation with a single atomic pointer swap. Readers then always
+/// see a complete and consistent store, and an update that fails leaves the current generation in service.
+///
+/// Copies are cheap: generations share the news and indexes, which are only copied when an update changes them.
 #[derive(Clone, Debug)]
 pub struct NewsState {
-  news_store: Arc<RwLock<NewsStore>>,
+  news_store: Arc<ArcSwap<NewsStore>>,
+
+  // serialize writers so that concurrent updates cannot overwrite each other’s generations
+  writer: Arc<Mutex<()>>,
 }
 
 impl NewsState {
   pub fn new(news_root_path: impl Into<PathBuf>) -> Self {
-    let news_store = Arc::new(RwLock::new(NewsStore::new(news_root_path)));
+    Self::from_store(NewsStore::new(news_root_path))
+  }
+
+  /// Create the state with an initial generation.
+  pub fn from_store(news_store: NewsStore) -> Self {
+    let news_store = Arc::new(ArcSwap::from_pointee(news_store));
+    let writer = Arc::new(Mutex::new(()));
+
+    Self { news_store, writer }
+  }
+
+  /// Get the current generation of the store.
+  pub fn news_store(&self) -> Arc<NewsStore> {
+    self.news_store.load_full()
+  }
+
+  /// Populate a copy of the current generation from its source and publish it.
+  ///
+  /// Only the news that changed are parsed again. If anything fails, the current generation is kept.
+  pub fn reload(&self) -> Result<Arc<NewsStore>, NewsError> {
+    let _writer = self.writer.lock().expect("news store writer");
+    let current = self.news_store.load_full();
+    let mut store = NewsStore::clone(&current);
+    store.populate_from_root()?;
 
-    Self { news_store }
+    Ok(self.publish(&current, store))
   }
 
-  pub fn news_store(&self) -> &Arc<RwLock<NewsStore>> {
-    &self.news_store
+  /// Apply an update to a copy of the current generation and publish it.
+  ///
+  /// If the update fails, the current generation is kept.
+  pub fn update(
+    &self,
+    f: impl FnOnce(&mut NewsStore) -> Result<(), NewsError>,
+  ) -> Result<Arc<NewsStore>, NewsError> {
+    let _writer = self.writer.lock().expect("news store writer");
+    let current = self.news_store.load_full();
+    let mut store = NewsStore::clone(&current);
+    f(&mut store)?;
+
+    Ok(self.publish(&current, store))
+  }
+
+  /// Replace the current generation, then persist what changed since the previous one.
+  fn publish(&self, previous: &NewsStore, store: NewsStore) -> Arc<NewsStore> {
+    let store = Arc::new(store);
+    self.news_store.store(store.clone());
+
+    if store.numbers() != previous.numbers() {
+      if let Err(err) = store.save_numbers() {
+        log::error!("cannot save issue numbers: {}", err);
+      }
+    }
+
+    if store.hashes != previous.hashes {
+      if let Err(err) = store.save_snapshot() {
+        log::error!("cannot save snapshot: {}", err);
+      }
+    }
+
+    store
   }
 }
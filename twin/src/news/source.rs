@@ -0,0 +1,476 @@
+//! Sources weekly news are read from.
+//!
+//! A [`NewsStore`](super::NewsStore) doesn’t read weekly news by itself: it asks a [`NewsSource`] to list them and to
+//! read their files. Whatever the source, news are laid out the same way, as `YYYY/Mon/DD.md` files or `YYYY/Mon/DD`
+//! directories.
+
+use super::{dir_name_to_day, file_name_to_day, LoadFailure, Month, NewsError, NewsFiles, NewsKey};
+use chrono::{DateTime, Utc};
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fmt::Debug,
+  fs::{self, DirEntry},
+  io::{self, Read},
+  path::{Component, Path, PathBuf},
+  sync::Arc,
+};
+
+/// Weekly news listed by a source, along with the entries that are not weekly news.
+pub type SourceEntries = Vec<Result<NewsKey, LoadFailure>>;
+
+/// Where weekly news are read from.
+///
+/// Paths of the failures reported by a source are relative to its root; the store reports them in its root path.
+pub trait NewsSource: Debug + Send + Sync {
+  /// List the weekly news.
+  ///
+  /// Errors preventing from listing anything are returned as such; entries that cannot be parsed as weekly news are
+  /// listed as failures, so that the store can decide what to do with them according to its load mode.
+  fn list(&self) -> Result<SourceEntries, NewsError>;
+
+  /// Read the files of a weekly news, if it exists.
+  fn read(&self, key: NewsKey) -> Result<Option<NewsFiles>, LoadFailure>;
+
+  /// Read an asset of a weekly news, if it exists.
+  ///
+  /// `asset` is the path of the asset relative to the directory of the news.
+  fn read_asset(&self, key: NewsKey, asset: &str) -> Option<Vec<u8>>;
+
+  /// Dates of the last changes of some weekly news, for sources knowing about them.
+  fn last_updated(&self, _keys: &[NewsKey]) -> Result<BTreeMap<NewsKey, DateTime<Utc>>, NewsError> {
+    Ok(BTreeMap::new())
+  }
+
+  /// Pin the source to its current state, such as the commit a git branch points to.
+  ///
+  /// A store reads from the pinned source until it is populated again, so that it never mixes several states of the
+  /// contents. Sources that don’t change on their own return `None`.
+  fn pin(&self) -> Result<Option<Arc<dyn NewsSource>>, NewsError> {
+    Ok(None)
+  }
+
+  /// Version of the contents of a pinned source, such as a commit id.
+  fn version(&self) -> Option<String> {
+    None
+  }
+}
+
+/// Weekly news read from a directory of the file system.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FsSource {
+  root: PathBuf,
+}
+
+impl FsSource {
+  pub fn new(root: impl Into<PathBuf>) -> Self {
+    Self { root: root.into() }
+  }
+
+  fn failure(&self, path: &Path, error: NewsError) -> LoadFailure {
+    LoadFailure {
+      path: path.strip_prefix(&self.root).unwrap_or(path).to_owned(),
+      error,
+    }
+  }
+
+  fn list_year(&self, entries: &mut SourceEntries, entry: DirEntry) {
+    let path = entry.path();
+    log::debug!("traversing year {}", path.display());
+
+    if !path.is_dir() {
+      return;
+    }
+
+    let result = entry
+      .file_name()
+      .to_str()
+      .and_then(|name| name.parse().ok())
+      .ok_or_else(|| NewsError::CannotParseYear(format!("{:?}", entry.file_name())))
+      .and_then(|year| Ok((year, fs::read_dir(&path)?)));
+
+    match result {
+      Ok((year, month_entries)) => {
+        for month_entry in month_entries.flatten() {
+          self.list_month(entries, month_entry, year);
+        }
+      }
+
+      Err(error) => entries.push(Err(self.failure(&path, error))),
+    }
+  }
+
+  fn list_month(&self, entries: &mut SourceEntries, entry: DirEntry, year: u16) {
+    let path = entry.path();
+    log::debug!("traversing month {}", path.display());
+
+    if !path.is_dir() {
+      return;
+    }
+
+    let result = entry
+      .file_name()
+      .to_str()
+      .and_then(|name| name.parse::<Month>().ok())
+      .ok_or_else(|| NewsError::CannotParseMonth(format!("{:?}", entry.file_name())))
+      .and_then(|month| Ok((month, fs::read_dir(&path)?)));
+
+    match result {
+      Ok((month, day_entries)) => {
+        for day_entry in day_entries.flatten() {
+          self.list_day(entries, day_entry, year, month);
+        }
+      }
+
+      Err(error) => entries.push(Err(self.failure(&path, error))),
+    }
+  }
+
+  fn list_day(&self, entries: &mut SourceEntries, entry: DirEntry, year: u16, month: Month) {
+    let path = entry.path();
+    log::debug!("found day {}", path.display());
+
+    let to_day = if path.is_file() {
+      file_name_to_day
+    } else if path.is_dir() {
+      dir_name_to_day
+    } else {
+      return;
+    };
+
+    let day = entry
+      .file_name()
+      .to_str()
+      .ok_or_else(|| NewsError::CannotParseDay(format!("{:?}", entry.file_name())))
+      .and_then(to_day);
+
+    match day {
+      Ok(day) => entries.push(Ok(NewsKey { year, month, day })),
+      Err(error) => entries.push(Err(self.failure(&path, error))),
+    }
+  }
+}
+
+impl NewsSource for FsSource {
+  fn list(&self) -> Result<SourceEntries, NewsError> {
+    let mut entries = Vec::new();
+
+    for entry in fs::read_dir(&self.root)?.flatten() {
+      self.list_year(&mut entries, entry);
+    }
+
+    Ok(entries)
+  }
+
+  fn read(&self, key: NewsKey) -> Result<Option<NewsFiles>, LoadFailure> {
+    let dir = key.to_dir_path(&self.root);
+    let path = key.to_file_path(&self.root);
+
+    if !dir.is_dir() {
+      if !path.is_file() {
+        return Ok(None);
+      }
+
+      log::debug!("reading news key: {:?} (path={})", key, path.display());
+      let md = fs::read_to_string(&path).map_err(|e| self.failure(&path, e.into()))?;
+      return Ok(Some(NewsFiles::File(md)));
+    }
+
+    log::debug!("reading news key: {:?}, (dir={})", key, dir.display());
+
+    let mut files = Vec::new();
+    collect_subdirs(&mut files, &dir).map_err(|(path, e)| self.failure(&path, e.into()))?;
+    let (files, assets) = split_assets(&dir, files);
+
+    let mds = files
+      .iter()
+      .map(|path| {
+        let md = fs::read_to_string(path).map_err(|e| self.failure(path, e.into()))?;
+        Ok((relative_path(&dir, path), md))
+      })
+      .collect::<Result<_, _>>()?;
+
+    Ok(Some(NewsFiles::Dir { mds, assets }))
+  }
+
+  fn read_asset(&self, key: NewsKey, asset: &str) -> Option<Vec<u8>> {
+    fs::read(key.to_dir_path(&self.root).join(asset)).ok()
+  }
+}
+
+/// Weekly news held in memory, as files indexed by their paths, such as `2022/Jun/06.md`.
+///
+/// This is mostly useful for tests and to embed contents in a program.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MemorySource {
+  files: BTreeMap<String, Vec<u8>>,
+}
+
+impl MemorySource {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Add a file, with `/` separators in its path.
+  pub fn with_file(mut self, path: impl AsRef<str>, content: impl Into<Vec<u8>>) -> Self {
+    self.insert(path, content);
+    self
+  }
+
+  /// Add or replace a file, with `/` separators in its path.
+  pub fn insert(&mut self, path: impl AsRef<str>, content: impl Into<Vec<u8>>) {
+    self.files.insert(normalize(path.as_ref()), content.into());
+  }
+
+  /// Remove a file, returning its content if it was present.
+  pub fn remove(&mut self, path: impl AsRef<str>) -> Option<Vec<u8>> {
+    self.files.remove(&normalize(path.as_ref()))
+  }
+
+  fn read_md(&self, path: &str, content: &[u8]) -> Result<String, LoadFailure> {
+    String::from_utf8(content.to_owned()).map_err(|e| LoadFailure {
+      path: PathBuf::from(path),
+      error: io::Error::new(io::ErrorKind::InvalidData, e).into(),
+    })
+  }
+}
+
+impl NewsSource for MemorySource {
+  fn list(&self) -> Result<SourceEntries, NewsError> {
+    let mut keys = BTreeSet::new();
+    let mut failures = BTreeMap::new();
+
+    for path in self.files.keys() {
+      let segments: Vec<_> = path.split('/').collect();
+
+      // as in a directory, only files in month directories can be weekly news
+      let (year, month, day) = match segments.as_slice() {
+        [year, month, day, rest @ ..] => (*year, *month, (*day, !rest.is_empty())),
+        _ => continue,
+      };
+
+      let year = match year.parse() {
+        Ok(year) => year,
+        Err(_) => {
+          failures.insert(year.to_owned(), NewsError::CannotParseYear(year.to_owned()));
+          continue;
+        }
+      };
+
+      let month_path = segments[..2].join("/");
+      let month = match month.parse() {
+        Ok(month) => month,
+        Err(_) => {
+          failures.insert(month_path, NewsError::CannotParseMonth(month.to_owned()));
+          continue;
+        }
+      };
+
+      let (day_name, is_dir) = day;
+      let day = if is_dir {
+        dir_name_to_day(day_name)
+      } else {
+        file_name_to_day(day_name)
+      };
+
+      match day {
+        Ok(day) => {
+          keys.insert(NewsKey { year, month, day });
+        }
+
+        Err(error) => {
+          failures.insert(segments[..3].join("/"), error);
+        }
+      }
+    }
+
+    let failures = failures.into_iter().map(|(path, error)| {
+      Err(LoadFailure {
+        path: PathBuf::from(path),
+        error,
+      })
+    });
+
+    Ok(keys.into_iter().map(Ok).chain(failures).collect())
+  }
+
+  fn read(&self, key: NewsKey) -> Result<Option<NewsFiles>, LoadFailure> {
+    let dir = format!("{}/{}/{:02}/", key.year, key.month, key.day);
+
+    // sort by path components, as files are glued according to a DFS of the directory
+    let mut files: Vec<_> = self
+      .files
+      .iter()
+      .filter_map(|(path, content)| Some((path.strip_prefix(&dir)?, path, content)))
+      .collect();
+    files.sort_by(|(a, ..), (b, ..)| a.split('/').cmp(b.split('/')));
+
+    if files.is_empty() {
+      let path = format!("{}/{}/{:02}.md", key.year, key.month, key.day);
+
+      return match self.files.get(&path) {
+        Some(content) => self
+          .read_md(&path, content)
+          .map(|md| Some(NewsFiles::File(md))),
+        None => Ok(None),
+      };
+    }
+
+    let mut mds = Vec::new();
+    let mut assets = Vec::new();
+
+    for (relative, path, content) in files {
+      let name = relative.rsplit_once('/').map_or(relative, |(_, name)| name);
+
+      if name.ends_with(".md") {
+        mds.push((relative.to_owned(), self.read_md(path, content)?));
+      } else if !name.starts_with('.') {
+        assets.push(relative.to_owned());
+      }
+    }
+
+    Ok(Some(NewsFiles::Dir { mds, assets }))
+  }
+
+  fn read_asset(&self, key: NewsKey, asset: &str) -> Option<Vec<u8>> {
+    let path = format!("{}/{}/{:02}/{}", key.year, key.month, key.day, asset);
+    self.files.get(&path).cloned()
+  }
+}
+
+/// Weekly news read from a tar archive.
+///
+/// The archive is read once and kept in memory. Paths in the archive are the same as in a directory, e.g.
+/// `2022/Jun/06.md`. An archive read from a file is read again every time the source is pinned, so that a store picks
+/// up a replaced archive when it is populated again.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TarSource {
+  files: MemorySource,
+  path: Option<PathBuf>,
+}
+
+impl TarSource {
+  /// Read a tar archive from a file.
+  pub fn open(path: impl Into<PathBuf>) -> Result<Self, NewsError> {
+    let path = path.into();
+    let source = Self::from_reader(fs::File::open(&path)?)?;
+
+    Ok(Self {
+      path: Some(path),
+      ..source
+    })
+  }
+
+  /// Read a tar archive.
+  pub fn from_reader(reader: impl Read) -> Result<Self, NewsError> {
+    let mut archive = tar::Archive::new(reader);
+    let mut files = MemorySource::new();
+
+    for entry in archive.entries()? {
+      let mut entry = entry?;
+
+      if !entry.header().entry_type().is_file() {
+        continue;
+      }
+
+      let path = entry.path()?;
+      let segments: Vec<_> = path
+        .components()
+        .filter_map(|component| match component {
+          Component::Normal(segment) => Some(segment.to_string_lossy().into_owned()),
+          _ => None,
+        })
+        .collect();
+      let path = segments.join("/");
+
+      let mut content = Vec::new();
+      entry.read_to_end(&mut content)?;
+      files.insert(path, content);
+    }
+
+    Ok(Self { files, path: None })
+  }
+}
+
+impl NewsSource for TarSource {
+  fn list(&self) -> Result<SourceEntries, NewsError> {
+    self.files.list()
+  }
+
+  fn read(&self, key: NewsKey) -> Result<Option<NewsFiles>, LoadFailure> {
+    self.files.read(key)
+  }
+
+  fn read_asset(&self, key: NewsKey, asset: &str) -> Option<Vec<u8>> {
+    self.files.read_asset(key, asset)
+  }
+
+  fn pin(&self) -> Result<Option<Arc<dyn NewsSource>>, NewsError> {
+    match self.path {
+      Some(ref path) => Ok(Some(Arc::new(Self::open(path)?))),
+      None => Ok(None),
+    }
+  }
+}
+
+/// Normalize a path given to a [`MemorySource`], removing empty and `.` segments.
+fn normalize(path: &str) -> String {
+  let segments: Vec<_> = path
+    .split('/')
+    .filter(|segment| !segment.is_empty() && *segment != ".")
+    .collect();
+
+  segments.join("/")
+}
+
+/// Collect the files of a directory, recursively, sorted by names and according to a DFS of the tree.
+///
+/// On error, the path that couldn’t be read is returned.
+fn collect_subdirs(files: &mut Vec<PathBuf>, file: &Path) -> Result<(), (PathBuf, io::Error)> {
+  if file.is_dir() {
+    let mut subfiles = fs::read_dir(file)
+      .map_err(|e| (file.to_owned(), e))?
+      .filter_map(|entry| entry.ok())
+      .map(|entry| entry.path())
+      .collect::<Vec<_>>();
+    subfiles.sort();
+
+    for subfile in subfiles {
+      collect_subdirs(files, &subfile)?;
+    }
+  } else {
+    files.push(file.to_owned());
+  }
+
+  Ok(())
+}
+
+/// Split the files of a news directory into Markdown files and assets.
+///
+/// Assets are returned as paths relative to the directory, with `/` separators. Hidden files are ignored.
+fn split_assets(dir: &Path, files: Vec<PathBuf>) -> (Vec<PathBuf>, Vec<String>) {
+  let (mds, others): (Vec<_>, Vec<_>) = files
+    .into_iter()
+    .partition(|path| path.extension().is_some_and(|ext| ext == "md"));
+  let assets = others
+    .iter()
+    .filter(|path| {
+      !path
+        .file_name()
+        .map_or(true, |name| name.to_string_lossy().starts_with('.'))
+    })
+    .map(|path| relative_path(dir, path))
+    .collect();
+
+  (mds, assets)
+}
+
+/// Path relative to a directory, with `/` separators.
+fn relative_path(dir: &Path, path: &Path) -> String {
+  let relative = path.strip_prefix(dir).unwrap_or(path);
+  let components: Vec<_> = relative
+    .components()
+    .map(|component| component.as_os_str().to_string_lossy())
+    .collect();
+
+  components.join("/")
+}
@@ -0,0 +1,159 @@
+//! Stable issue numbers.
+//!
+//! Weekly news are referred to by their numbers (e.g. “TWiN #42”). Those numbers must not depend on the position of
+//! the news in the listings; otherwise, adding a back-dated news or removing one would renumber every other news.
+//! Numbers are then assigned once and for all, and can be persisted to disk to survive restarts.
+
+use crate::news::{News, NewsError, NewsKey};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fs, io,
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+
+/// Numbers assigned to weekly news.
+///
+/// News can still override their number via their metadata, in which case the assigned number is ignored.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IssueNumbers {
+  path: Option<PathBuf>,
+  by_key: BTreeMap<NewsKey, u32>,
+}
+
+/// On-disk representation of [`IssueNumbers`].
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct PersistedNumbers {
+  #[serde(default)]
+  issues: Vec<PersistedNumber>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct PersistedNumber {
+  number: u32,
+  key: NewsKey,
+}
+
+impl IssueNumbers {
+  /// Load the numbers persisted at the given path.
+  ///
+  /// If the file doesn’t exist yet, no number is assigned; the file will be created on the first assignment.
+  pub fn load(path: impl Into<PathBuf>) -> Result<Self, NewsError> {
+    let path = path.into();
+
+    let persisted: PersistedNumbers = match fs::read_to_string(&path) {
+      Ok(content) => {
+        toml::from_str(&content).map_err(|e| NewsError::CannotParseNumbers(e.to_string()))?
+      }
+      Err(e) if e.kind() == io::ErrorKind::NotFound => PersistedNumbers::default(),
+      Err(e) => return Err(e.into()),
+    };
+
+    let by_key = persisted
+      .issues
+      .into_iter()
+      .map(|issue| (issue.key, issue.number))
+      .collect();
+
+    Ok(Self {
+      path: Some(path),
+      by_key,
+    })
+  }
+
+  /// Path where the numbers are persisted, if any.
+  pub fn path(&self) -> Option<&Path> {
+    self.path.as_deref()
+  }
+
+  /// Number assigned to a key, if any.
+  pub fn get(&self, key: &NewsKey) -> Option<u32> {
+    self.by_key.get(key).copied()
+  }
+
+  /// Highest assigned number, if any; it might belong to a news that is not present anymore.
+  pub fn last(&self) -> Option<u32> {
+    self.by_key.values().copied().max()
+  }
+
+  /// Assign numbers to news that don’t have one yet, from the oldest to the most recent one.
+  ///
+  /// A news gets the number following the one of the previous news if it’s free, or the number following the highest
+  /// number in use otherwise (e.g. for back-dated news). News with a number in their metadata are skipped. Already
+  /// assigned numbers are never changed, even for news that are not present anymore, so that they get their number
+  /// back if they are added again.
+  ///
+  /// News that failed to load are numbered as well, in `failed`, so that the news following them don’t take their
+  /// numbers; they keep them once fixed.
+  ///
+  /// Return whether any new number was assigned.
+  pub fn assign(
+    &mut self,
+    news: &BTreeMap<NewsKey, Arc<News>>,
+    failed: &BTreeSet<NewsKey>,
+  ) -> bool {
+    let keys: BTreeSet<_> = news.keys().chain(failed).copied().collect();
+    let mut used: BTreeSet<_> = self
+      .by_key
+      .values()
+      .copied()
+      .chain(news.values().filter_map(|news| news.metadata.number))
+      .collect();
+    let mut previous = 0;
+    let mut assigned = false;
+
+    for key in keys {
+      let number = match news
+        .get(&key)
+        .and_then(|news| news.metadata.number)
+        .or_else(|| self.get(&key))
+      {
+        Some(number) => number,
+
+        None => {
+          let number = if used.contains(&(previous + 1)) {
+            used.iter().next_back().map_or(1, |last| last + 1)
+          } else {
+            previous + 1
+          };
+
+          self.by_key.insert(key, number);
+          used.insert(number);
+          assigned = true;
+
+          log::debug!("assigning number {} to news {:?}", number, key);
+          number
+        }
+      };
+
+      previous = previous.max(number);
+    }
+
+    assigned
+  }
+
+  /// Persist the numbers, if a path was provided.
+  pub fn save(&self) -> Result<(), NewsError> {
+    let path = match self.path {
+      Some(ref path) => path,
+      None => return Ok(()),
+    };
+
+    let persisted = PersistedNumbers {
+      issues: self
+        .by_key
+        .iter()
+        .map(|(key, number)| PersistedNumber {
+          number: *number,
+          key: *key,
+        })
+        .collect(),
+    };
+    let content =
+      toml::to_string(&persisted).map_err(|e| NewsError::CannotParseNumbers(e.to_string()))?;
+    fs::write(path, content)?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,225 @@
+//! Index of the repositories mentioned in weekly news.
+//!
+//! Most plugins are announced with a link to their GitHub repository. Those links are extracted from every weekly news,
+//! so that the whole history of a plugin can be followed across issues.
+
+use crate::{
+  issue::{Link, Section},
+  news::{News, NewsKey},
+};
+use std::{collections::BTreeMap, fmt::Display};
+
+/// First path segments of GitHub URLs that are not repositories.
+const RESERVED_OWNERS: [&str; 14] = [
+  "about",
+  "apps",
+  "collections",
+  "explore",
+  "features",
+  "login",
+  "marketplace",
+  "notifications",
+  "orgs",
+  "search",
+  "settings",
+  "sponsors",
+  "topics",
+  "users",
+];
+
+/// A GitHub repository.
+///
+/// Owners and names are lowercased, as GitHub doesn’t care about case.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Repository {
+  pub owner: String,
+  pub name: String,
+}
+
+impl Display for Repository {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}/{}", self.owner, self.name)
+  }
+}
+
+impl Repository {
+  pub fn new(owner: impl AsRef<str>, name: impl AsRef<str>) -> Self {
+    Self {
+      owner: owner.as_ref().to_lowercase(),
+      name: name.as_ref().to_lowercase(),
+    }
+  }
+
+  /// Extract the repository a GitHub URL points to, such as `https://github.com/owner/name/pull/1`.
+  pub fn from_url(url: &str) -> Option<Self> {
+    let rest = url
+      .strip_prefix("https://")
+      .or_else(|| url.strip_prefix("http://"))?;
+    let rest = rest.strip_prefix("www.").unwrap_or(rest);
+    let path = rest.strip_prefix("github.com/")?;
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+
+    let mut segments = path.split('/');
+    let owner = segments.next().filter(|owner| !owner.is_empty())?;
+    let name = segments.next()?;
+    let name = name.strip_suffix(".git").unwrap_or(name);
+
+    if name.is_empty() || RESERVED_OWNERS.contains(&owner.to_lowercase().as_str()) {
+      return None;
+    }
+
+    Some(Self::new(owner, name))
+  }
+
+  /// URL of the repository on GitHub.
+  pub fn url(&self) -> String {
+    format!("https://github.com/{}/{}", self.owner, self.name)
+  }
+}
+
+/// An appearance of a repository in a weekly news.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Mention {
+  pub key: NewsKey,
+
+  /// Identifier of the heading of the item or section mentioning the repository; the intro doesn’t have any.
+  pub anchor: Option<String>,
+
+  /// Plain text version of the heading of the item or section mentioning the repository; empty for the intro.
+  pub heading: String,
+
+  /// Text of the first link to the repository.
+  pub text: String,
+}
+
+/// Extract the repositories mentioned in a weekly news, in order of appearance.
+///
+/// A repository linked several times in the same item or section is mentioned only once.
+pub fn mentions(key: NewsKey, news: &News) -> Vec<(Repository, Mention)> {
+  fn push(
+    mentions: &mut Vec<(Repository, Mention)>,
+    key: NewsKey,
+    anchor: Option<&str>,
+    heading: &str,
+    links: &[Link],
+  ) {
+    for link in links {
+      let repository = match Repository::from_url(&link.url) {
+        Some(repository) => repository,
+        None => continue,
+      };
+
+      let already_mentioned = mentions.iter().any(|(r, mention)| {
+        *r == repository && mention.key == key && mention.anchor.as_deref() == anchor
+      });
+
+      if !already_mentioned {
+        let mention = Mention {
+          key,
+          anchor: anchor.map(str::to_owned),
+          heading: heading.to_owned(),
+          text: link.text.clone(),
+        };
+        mentions.push((repository, mention));
+      }
+    }
+  }
+
+  fn push_section(mentions: &mut Vec<(Repository, Mention)>, key: NewsKey, section: &Section) {
+    let heading = &section.heading;
+    push(
+      mentions,
+      key,
+      Some(&heading.id),
+      &heading.text,
+      &section.links,
+    );
+
+    for item in &section.items {
+      let heading = &item.heading;
+      push(mentions, key, Some(&heading.id), &heading.text, &item.links);
+    }
+
+    for subsection in &section.subsections {
+      push_section(mentions, key, subsection);
+    }
+  }
+
+  let issue = &news.issue;
+  let mut mentions = Vec::new();
+  push(&mut mentions, key, None, "", &issue.intro_links);
+
+  for item in &issue.items {
+    let heading = &item.heading;
+    push(
+      &mut mentions,
+      key,
+      Some(&heading.id),
+      &heading.text,
+      &item.links,
+    );
+  }
+
+  for section in &issue.sections {
+    push_section(&mut mentions, key, section);
+  }
+
+  mentions
+}
+
+/// Index of every repository mentioned in weekly news, to the news mentioning them.
+#[derive(Clone, Debug, Default)]
+pub struct PluginIndex {
+  mentions: BTreeMap<Repository, Vec<Mention>>,
+}
+
+impl PluginIndex {
+  /// Build the index of several weekly news.
+  pub fn from_news<'a>(news: impl IntoIterator<Item = (&'a NewsKey, &'a News)>) -> Self {
+    let mut index = Self::default();
+
+    for (key, news) in news {
+      index.insert(*key, news);
+    }
+
+    index
+  }
+
+  /// Index a weekly news, replacing its previous version if any.
+  pub fn insert(&mut self, key: NewsKey, news: &News) {
+    self.remove(key);
+
+    for (repository, mention) in mentions(key, news) {
+      let repository_mentions = self.mentions.entry(repository).or_default();
+      repository_mentions.push(mention);
+
+      // keep mentions sorted from the oldest to the most recent news; the sort is stable, so mentions in the same news
+      // stay in order of appearance
+      repository_mentions.sort_by_key(|mention| mention.key);
+    }
+  }
+
+  /// Remove a weekly news from the index.
+  pub fn remove(&mut self, key: NewsKey) {
+    self.mentions.retain(|_, mentions| {
+      mentions.retain(|mention| mention.key != key);
+      !mentions.is_empty()
+    });
+  }
+
+  /// Get every mentioned repository along with its mentions, sorted by repository.
+  pub fn repositories(&self) -> impl Iterator<Item = (&Repository, &[Mention])> {
+    self
+      .mentions
+      .iter()
+      .map(|(repository, mentions)| (repository, mentions.as_slice()))
+  }
+
+  /// Get the mentions of a repository, from the oldest to the most recent news.
+  pub fn mentions(&self, repository: &Repository) -> &[Mention] {
+    self
+      .mentions
+      .get(repository)
+      .map_or(&[], |mentions| mentions.as_slice())
+  }
+}
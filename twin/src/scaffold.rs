@@ -0,0 +1,199 @@
+//! Scaffolding of new weekly news.
+
+use crate::{
+  news::{LoadMode, NewsError, NewsKey, NewsStore},
+  numbers::IssueNumbers,
+};
+use chrono::{Duration, NaiveDate};
+use std::{
+  fmt::Display,
+  fs, io,
+  path::{Path, PathBuf},
+};
+
+/// Number of days between two weekly news.
+const DAYS_BETWEEN_NEWS: i64 = 7;
+
+/// Layout of a weekly news on disk.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Layout {
+  /// A single Markdown file, e.g. `2022/Jun/27.md`.
+  File,
+
+  /// A directory with one Markdown file per section, e.g. `2022/Jun/27/…`.
+  #[default]
+  Directory,
+}
+
+#[derive(Debug)]
+pub enum ScaffoldError {
+  News(NewsError),
+  NoPreviousNews,
+  InvalidDate(NaiveDate),
+  AlreadyExists(PathBuf),
+}
+
+impl Display for ScaffoldError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ScaffoldError::News(e) => write!(f, "{}", e),
+      ScaffoldError::NoPreviousNews => {
+        f.write_str("no previous news to compute the date from; please provide a date")
+      }
+      ScaffoldError::InvalidDate(date) => write!(f, "unsupported date: {}", date),
+      ScaffoldError::AlreadyExists(path) => write!(f, "news already exists: {}", path.display()),
+    }
+  }
+}
+
+impl From<NewsError> for ScaffoldError {
+  fn from(e: NewsError) -> Self {
+    Self::News(e)
+  }
+}
+
+impl From<io::Error> for ScaffoldError {
+  fn from(e: io::Error) -> Self {
+    Self::News(e.into())
+  }
+}
+
+/// A newly scaffolded weekly news.
+#[derive(Clone, Debug)]
+pub struct Scaffolded {
+  pub key: NewsKey,
+  pub number: u32,
+
+  /// Created files.
+  pub files: Vec<PathBuf>,
+}
+
+/// Create the next weekly news in the contents tree at the given root.
+///
+/// If no date is provided, the news is dated one week after the latest news. The news is created with the standard
+/// sections and a front matter to fill. An existing news is never overwritten.
+///
+/// The news gets the number following every number in use, including the already assigned `numbers` (e.g. persisted
+/// by the backend), which might belong to news not present anymore.
+pub fn scaffold(
+  root_path: impl Into<PathBuf>,
+  date: Option<NaiveDate>,
+  layout: Layout,
+  numbers: IssueNumbers,
+) -> Result<Scaffolded, ScaffoldError> {
+  let root_path = root_path.into();
+  let mut store = NewsStore::new(&root_path)
+    .with_load_mode(LoadMode::Lenient)
+    .with_numbers(numbers);
+  store.populate_from_root()?;
+
+  let date = match date {
+    Some(date) => date,
+    // drafts and scheduled news count, so that the next news follows them
+    None => store
+      .keys()
+      .chain(store.unpublished_keys())
+      .max()
+      .and_then(NewsKey::to_date)
+      .ok_or(ScaffoldError::NoPreviousNews)?
+      .checked_add_signed(Duration::days(DAYS_BETWEEN_NEWS))
+      .ok_or(ScaffoldError::NoPreviousNews)?,
+  };
+  let key = NewsKey::from_date(date).ok_or(ScaffoldError::InvalidDate(date))?;
+
+  for path in [key.to_file_path(&root_path), key.to_dir_path(&root_path)] {
+    if path.exists() {
+      return Err(ScaffoldError::AlreadyExists(path));
+    }
+  }
+
+  let unpublished_numbers = store
+    .unpublished_keys()
+    .filter_map(|key| store.get_unpublished(key)?.metadata.number);
+  let number = store
+    .keys()
+    .filter_map(|key| store.number(key))
+    .chain(unpublished_numbers)
+    .chain(store.numbers().last())
+    .max()
+    .unwrap_or(0)
+    + 1;
+  let front_matter = front_matter(number, date);
+
+  let files = match layout {
+    Layout::File => {
+      let path = key.to_file_path(&root_path);
+      let content = format!("{}\n{}\n{}", front_matter, CORE_SKELETON, PLUGINS_SKELETON);
+      vec![(path, content)]
+    }
+
+    Layout::Directory => {
+      let dir = key.to_dir_path(&root_path);
+      vec![
+        (dir.join("00-intro.md"), front_matter),
+        (dir.join("01-core.md"), CORE_SKELETON.to_owned()),
+        (dir.join("02-plugins.md"), PLUGINS_SKELETON.to_owned()),
+      ]
+    }
+  };
+
+  for (path, content) in &files {
+    create_new_file(path, content)?;
+  }
+
+  Ok(Scaffolded {
+    key,
+    number,
+    files: files.into_iter().map(|(path, _)| path).collect(),
+  })
+}
+
+/// Create a file, failing if it already exists.
+fn create_new_file(path: &Path, content: &str) -> Result<(), ScaffoldError> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+
+  let mut file = fs::OpenOptions::new()
+    .write(true)
+    .create_new(true)
+    .open(path)
+    .map_err(|e| match e.kind() {
+      io::ErrorKind::AlreadyExists => ScaffoldError::AlreadyExists(path.to_owned()),
+      _ => e.into(),
+    })?;
+  io::Write::write_all(&mut file, content.as_bytes())?;
+
+  Ok(())
+}
+
+fn front_matter(number: u32, date: NaiveDate) -> String {
+  format!(
+    r#"+++
+title = "This Week in Neovim #{number}"
+number = {number}
+date = {date}
+authors = []
+editors = []
+tags = []
+# summary = ""
++++
+"#,
+    number = number,
+    date = date
+  )
+}
+
+const CORE_SKELETON: &str = "# Neovim core
+
+## Nightly
+
+## Stable
+";
+
+const PLUGINS_SKELETON: &str = "# Plugins
+
+## New plugins
+
+## Updates
+";
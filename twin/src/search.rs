@@ -0,0 +1,370 @@
+//! Full-text search across weekly news.
+//!
+//! Every weekly news is split into passages — its introduction, and the body of each of its sections and items — which
+//! are indexed in an inverted index. Searching returns passages, so that results can point to the heading anchor of the
+//! matching part of a news.
+
+use crate::{
+  issue::{Item, Section},
+  news::{News, NewsKey},
+};
+use std::{
+  cmp::Ordering,
+  collections::{BTreeMap, BTreeSet},
+  ops::{Bound, Range},
+};
+
+/// Weight of a word found in the heading of a passage, relative to a word found in its body.
+const HEADING_WEIGHT: u32 = 3;
+
+/// Weight of a word matching a search term only by prefix, relative to an exact match.
+const PREFIX_WEIGHT: f64 = 0.5;
+
+/// Maximum length of a snippet, in bytes.
+const SNIPPET_LEN: usize = 200;
+
+/// Length of the context kept before the first match in a snippet, in bytes.
+const SNIPPET_CONTEXT: usize = 60;
+
+/// HTML entities produced when rendering Markdown to HTML.
+const ENTITIES: [(&str, &str); 4] = [
+  ("&amp;", "&"),
+  ("&lt;", "<"),
+  ("&gt;", ">"),
+  ("&quot;", "\""),
+];
+
+/// HTML tags that don’t separate words.
+const INLINE_TAGS: [&str; 9] = [
+  "a", "code", "del", "em", "img", "span", "strong", "sub", "sup",
+];
+
+/// A passage is identified by the key of its news and its position in the news.
+type PassageId = (NewsKey, usize);
+
+/// Inverted index of the passages of weekly news.
+#[derive(Clone, Debug, Default)]
+pub struct SearchIndex {
+  passages: BTreeMap<PassageId, Passage>,
+
+  // weighted number of occurrences of every word in the passages containing it
+  postings: BTreeMap<String, BTreeMap<PassageId, u32>>,
+}
+
+#[derive(Clone, Debug)]
+struct Passage {
+  anchor: Option<String>,
+  heading: String,
+  text: String,
+
+  // distinct words of the passage, to clean the postings when it’s removed
+  words: Vec<String>,
+}
+
+/// A passage of a weekly news matching a search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+  pub key: NewsKey,
+
+  /// Identifier of the heading of the passage, if any; the introduction of a news doesn’t have any.
+  pub anchor: Option<String>,
+
+  /// Plain text version of the heading of the passage; empty for the introduction of a news.
+  pub heading: String,
+
+  pub score: f64,
+  pub snippet: Snippet,
+}
+
+/// Excerpt of the text of a passage around the words matching a search.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Snippet {
+  pub text: String,
+
+  /// Byte ranges of the matching words in the text, sorted.
+  pub highlights: Vec<Range<usize>>,
+}
+
+impl SearchIndex {
+  /// Build the index of several weekly news.
+  pub fn from_news<'a>(news: impl IntoIterator<Item = (&'a NewsKey, &'a News)>) -> Self {
+    let mut index = Self::default();
+
+    for (key, news) in news {
+      index.insert(*key, news);
+    }
+
+    index
+  }
+
+  /// Index a weekly news, replacing its previous version if any.
+  pub fn insert(&mut self, key: NewsKey, news: &News) {
+    self.remove(key);
+
+    for (i, mut passage) in passages(news).into_iter().enumerate() {
+      let id = (key, i);
+      let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+
+      for (_, word) in words(&passage.heading) {
+        *counts.entry(word).or_default() += HEADING_WEIGHT;
+      }
+
+      for (_, word) in words(&passage.text) {
+        *counts.entry(word).or_default() += 1;
+      }
+
+      passage.words = counts.keys().cloned().collect();
+
+      for (word, count) in counts {
+        self.postings.entry(word).or_default().insert(id, count);
+      }
+
+      self.passages.insert(id, passage);
+    }
+  }
+
+  /// Remove a weekly news from the index.
+  pub fn remove(&mut self, key: NewsKey) {
+    let ids: Vec<_> = self
+      .passages
+      .range((key, 0)..=(key, usize::MAX))
+      .map(|(id, _)| *id)
+      .collect();
+
+    for id in ids {
+      let passage = match self.passages.remove(&id) {
+        Some(passage) => passage,
+        None => continue,
+      };
+
+      for word in passage.words {
+        if let Some(postings) = self.postings.get_mut(&word) {
+          postings.remove(&id);
+
+          if postings.is_empty() {
+            self.postings.remove(&word);
+          }
+        }
+      }
+    }
+  }
+
+  /// Search passages containing every word of the query, returning at most `limit` results, best ones first.
+  ///
+  /// Words of the query also match longer words starting with them (e.g. `tele` matches `telescope`), with a lower
+  /// score. Ties are broken by showing the most recent news first.
+  pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+    let terms: BTreeSet<_> = words(query).into_iter().map(|(_, word)| word).collect();
+    let passages_len = self.passages.len() as f64;
+    let mut scores: Option<BTreeMap<PassageId, f64>> = None;
+
+    for term in &terms {
+      let mut term_scores = BTreeMap::new();
+      let matching = self
+        .postings
+        .range::<str, _>((Bound::Included(term.as_str()), Bound::Unbounded))
+        .take_while(|(word, _)| word.starts_with(term.as_str()));
+
+      for (word, postings) in matching {
+        let idf = (1. + passages_len / postings.len() as f64).ln();
+        let weight = if word == term { 1. } else { PREFIX_WEIGHT };
+
+        for (id, count) in postings {
+          *term_scores.entry(*id).or_insert(0.) += f64::from(*count) * idf * weight;
+        }
+      }
+
+      // only keep passages matching every term
+      scores = Some(match scores {
+        None => term_scores,
+        Some(scores) => scores
+          .into_iter()
+          .filter_map(|(id, score)| term_scores.get(&id).map(|s| (id, score + s)))
+          .collect(),
+      });
+    }
+
+    let mut scores: Vec<_> = scores.unwrap_or_default().into_iter().collect();
+    scores.sort_by(|(a_id, a), (b_id, b)| {
+      b.partial_cmp(a)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| b_id.0.cmp(&a_id.0))
+        .then_with(|| a_id.1.cmp(&b_id.1))
+    });
+
+    scores
+      .into_iter()
+      .take(limit)
+      .filter_map(|(id, score)| {
+        let passage = self.passages.get(&id)?;
+
+        Some(SearchResult {
+          key: id.0,
+          anchor: passage.anchor.clone(),
+          heading: passage.heading.clone(),
+          score,
+          snippet: snippet(&passage.text, &terms),
+        })
+      })
+      .collect()
+  }
+}
+
+impl Passage {
+  fn new(anchor: Option<String>, heading: String, html: &str) -> Self {
+    Self {
+      anchor,
+      heading,
+      text: html_to_text(html),
+      words: Vec::new(),
+    }
+  }
+
+  fn from_item(item: &Item) -> Self {
+    Self::new(
+      Some(item.heading.id.clone()),
+      item.heading.text.clone(),
+      &item.body,
+    )
+  }
+}
+
+/// Split a weekly news into passages, in order.
+fn passages(news: &News) -> Vec<Passage> {
+  fn push_section(passages: &mut Vec<Passage>, section: &Section) {
+    passages.push(Passage::new(
+      Some(section.heading.id.clone()),
+      section.heading.text.clone(),
+      &section.body,
+    ));
+    passages.extend(section.items.iter().map(Passage::from_item));
+
+    for subsection in &section.subsections {
+      push_section(passages, subsection);
+    }
+  }
+
+  let issue = &news.issue;
+  let mut intro = Passage::new(None, String::new(), &issue.intro);
+
+  if let Some(ref summary) = news.metadata.summary {
+    intro.text = format!("{} {}", summary, intro.text).trim().to_owned();
+  }
+
+  let mut passages = vec![intro];
+  passages.extend(issue.items.iter().map(Passage::from_item));
+
+  for section in &issue.sections {
+    push_section(&mut passages, section);
+  }
+
+  passages.retain(|passage| !passage.heading.is_empty() || !passage.text.is_empty());
+  passages
+}
+
+/// Split some text into lowercase words, along with their byte ranges.
+fn words(text: &str) -> Vec<(Range<usize>, String)> {
+  let mut words = Vec::new();
+  let mut start = None;
+
+  for (i, c) in text.char_indices().chain(Some((text.len(), ' '))) {
+    match (c.is_alphanumeric(), start) {
+      (true, None) => start = Some(i),
+
+      (false, Some(s)) => {
+        words.push((s..i, text[s..i].to_lowercase()));
+        start = None;
+      }
+
+      _ => (),
+    }
+  }
+
+  words
+}
+
+/// Extract the part of a text around the first word matching a term, highlighting all matching words.
+fn snippet(text: &str, terms: &BTreeSet<String>) -> Snippet {
+  let matches: Vec<_> = words(text)
+    .into_iter()
+    .filter(|(_, word)| terms.iter().any(|term| word.starts_with(term.as_str())))
+    .map(|(range, _)| range)
+    .collect();
+  let first = matches.first().map_or(0, |range| range.start);
+
+  // start and end on word boundaries
+  let mut start = floor_char_boundary(text, first.saturating_sub(SNIPPET_CONTEXT));
+  if start > 0 {
+    start += text[start..first]
+      .find(char::is_whitespace)
+      .map_or(0, |i| i + 1);
+  }
+
+  let mut end = floor_char_boundary(text, start + SNIPPET_LEN);
+  if end < text.len() {
+    end = text[start..end]
+      .rfind(char::is_whitespace)
+      .map_or(end, |i| start + i);
+  }
+
+  let prefix = if start > 0 { "… " } else { "" };
+  let suffix = if end < text.len() { " …" } else { "" };
+  let highlights = matches
+    .into_iter()
+    .filter(|range| range.start >= start && range.end <= end)
+    .map(|range| range.start - start + prefix.len()..range.end - start + prefix.len())
+    .collect();
+
+  Snippet {
+    text: format!("{}{}{}", prefix, &text[start..end], suffix),
+    highlights,
+  }
+}
+
+fn floor_char_boundary(text: &str, mut i: usize) -> usize {
+  if i >= text.len() {
+    return text.len();
+  }
+
+  while !text.is_char_boundary(i) {
+    i -= 1;
+  }
+
+  i
+}
+
+/// Plain text of some HTML, with tags removed, entities decoded and whitespace collapsed.
+fn html_to_text(html: &str) -> String {
+  let mut text = String::with_capacity(html.len());
+  let mut rest = html;
+
+  while let Some(i) = rest.find(['<', '&']) {
+    text.push_str(&rest[..i]);
+    rest = &rest[i..];
+
+    if rest.starts_with('<') {
+      let end = rest.find('>').map_or(rest.len(), |i| i + 1);
+      let name: String = rest[1..end]
+        .trim_start_matches('/')
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect();
+
+      if !INLINE_TAGS.contains(&name.as_str()) {
+        text.push(' ');
+      }
+
+      rest = &rest[end..];
+    } else {
+      let (entity, decoded) = ENTITIES
+        .iter()
+        .find(|(entity, _)| rest.starts_with(entity))
+        .map_or(("&", "&"), |(entity, decoded)| (*entity, *decoded));
+      text.push_str(decoded);
+      rest = &rest[entity.len()..];
+    }
+  }
+
+  text.push_str(rest);
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,110 @@
+//! Snapshots of parsed weekly news.
+//!
+//! Parsing all the news (and highlighting their code blocks) takes a while. A [`Snapshot`] persists the parsed news
+//! along with a hash of the files each of them was parsed from, so that a [`NewsStore`] can serve them right away when
+//! starting, and parse again only the news whose files changed in the meantime.
+//!
+//! [`NewsStore`]: crate::news::NewsStore
+
+use crate::news::{News, NewsError, NewsFiles, NewsKey, ParseOptions};
+use fnv::FnvHasher;
+use serde::{Deserialize, Serialize};
+use std::{
+  fs,
+  hash::{Hash as _, Hasher as _},
+  io,
+  path::Path,
+  sync::Arc,
+};
+
+/// Version of the on-disk format of snapshots; snapshots of other versions are rejected.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Parsed weekly news, as persisted on disk.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+  pub version: u32,
+
+  /// What the parsed news depend on besides their files (see [`fingerprint`]).
+  pub fingerprint: String,
+
+  pub news: Vec<SnapshotEntry>,
+}
+
+/// A parsed weekly news, along with the hash of the files it was parsed from.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SnapshotEntry {
+  pub key: NewsKey,
+  pub hash: u64,
+  pub news: Arc<News>,
+}
+
+// used to check the version before trying to read the rest of a snapshot
+#[derive(Deserialize)]
+struct SnapshotHeader {
+  version: u32,
+}
+
+impl Snapshot {
+  pub fn new(fingerprint: impl Into<String>, news: Vec<SnapshotEntry>) -> Self {
+    Self {
+      version: SNAPSHOT_VERSION,
+      fingerprint: fingerprint.into(),
+      news,
+    }
+  }
+
+  /// Load the snapshot saved at the given path, if any.
+  pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>, NewsError> {
+    let content = match fs::read(path) {
+      Ok(content) => content,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e.into()),
+    };
+
+    let header: SnapshotHeader = serde_json::from_slice(&content).map_err(snapshot_error)?;
+    if header.version != SNAPSHOT_VERSION {
+      return Err(NewsError::InvalidSnapshot(format!(
+        "unsupported version {} (expected {})",
+        header.version, SNAPSHOT_VERSION
+      )));
+    }
+
+    serde_json::from_slice(&content)
+      .map(Some)
+      .map_err(snapshot_error)
+  }
+
+  /// Save the snapshot at the given path.
+  ///
+  /// The snapshot is written to a temporary file first, so that a crash never leaves a partial snapshot behind.
+  pub fn save(&self, path: impl AsRef<Path>) -> Result<(), NewsError> {
+    let path = path.as_ref();
+    let content = serde_json::to_vec(self).map_err(snapshot_error)?;
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(tmp_path, path)?;
+
+    Ok(())
+  }
+}
+
+/// Hash the files of a weekly news, to know whether it must be parsed again.
+pub fn hash_files(files: &NewsFiles) -> u64 {
+  let mut hasher = FnvHasher::default();
+  files.hash(&mut hasher);
+  hasher.finish()
+}
+
+/// Fingerprint of the parsing of weekly news: the version of twin and the parse options.
+///
+/// News parsed with another fingerprint must be parsed again, even if their files didn’t change.
+pub fn fingerprint(parse_options: &ParseOptions) -> String {
+  let mut hasher = FnvHasher::default();
+  parse_options.hash(&mut hasher);
+  format!("{}-{:016x}", env!("CARGO_PKG_VERSION"), hasher.finish())
+}
+
+fn snapshot_error(e: serde_json::Error) -> NewsError {
+  NewsError::InvalidSnapshot(e.to_string())
+}
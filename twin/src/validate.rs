@@ -0,0 +1,278 @@
+//! Validation of a contents tree.
+//!
+//! This is meant to be run on the contents repository before changes are merged, so that problems are caught before
+//! the backend tries to load them.
+
+use crate::news::{split_front_matter, LoadMode, News, NewsError, NewsKey, NewsStore};
+use pulldown_cmark::{BrokenLink, Event, HeadingLevel, Options, Parser, Tag};
+use std::{
+  collections::BTreeSet,
+  fmt::Display,
+  path::{Path, PathBuf},
+};
+
+/// Options of the validation.
+#[derive(Clone, Debug)]
+pub struct ValidationOptions {
+  /// Sections every weekly news must have.
+  ///
+  /// A section is found if the text of a top-level section heading contains the name, ignoring case.
+  pub required_sections: Vec<String>,
+}
+
+impl Default for ValidationOptions {
+  fn default() -> Self {
+    Self {
+      required_sections: vec!["core".to_owned(), "plugin".to_owned()],
+    }
+  }
+}
+
+/// A problem found in the contents.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Problem {
+  pub path: PathBuf,
+
+  /// Line of the problem in the file, starting at 1, if it applies.
+  pub line: Option<usize>,
+
+  pub message: String,
+}
+
+impl Display for Problem {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.line {
+      Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+      None => write!(f, "{}: {}", self.path.display(), self.message),
+    }
+  }
+}
+
+/// Validate the contents tree at the given root and report every problem found, sorted by path and line.
+///
+/// The root is either a directory or a tar archive of one (see [`NewsStore::open`]). An error is returned only if the
+/// root itself cannot be read.
+pub fn validate(
+  root_path: impl Into<PathBuf>,
+  options: &ValidationOptions,
+) -> Result<Vec<Problem>, NewsError> {
+  let mut store = NewsStore::open(root_path)?.with_load_mode(LoadMode::Lenient);
+  store.populate_from_root()?;
+
+  let mut problems = Vec::new();
+  let mut keys: BTreeSet<_> = store
+    .keys()
+    .chain(store.unpublished_keys())
+    .copied()
+    .collect();
+
+  for failure in &store.report().failures {
+    keys.extend(store.key_from_path(&failure.path));
+
+    // front matter problems are reported file by file, with their lines
+    if !matches!(failure.error, NewsError::CannotParseFrontMatter(_)) {
+      problems.push(Problem {
+        path: failure.path.clone(),
+        line: None,
+        message: failure.error.to_string(),
+      });
+    }
+  }
+
+  for key in keys {
+    validate_news(&store, key, options, &mut problems);
+  }
+
+  problems.sort();
+  problems.dedup();
+
+  Ok(problems)
+}
+
+fn validate_news(
+  store: &NewsStore,
+  key: NewsKey,
+  options: &ValidationOptions,
+  problems: &mut Vec<Problem>,
+) {
+  let files = match store.files(key) {
+    Ok(files) => files,
+    Err(_) => return, // already reported by the store
+  };
+  let news_path = files
+    .first()
+    .map(|(path, _)| path.clone())
+    .unwrap_or_default();
+
+  if key.to_date().is_none() {
+    problems.push(Problem {
+      path: news_path.clone(),
+      line: None,
+      message: format!("impossible date: {} {} {}", key.day, key.month, key.year),
+    });
+  }
+
+  for (path, content) in &files {
+    validate_file(path, content, problems);
+  }
+
+  if let Some(news) = store.get(&key).or_else(|| store.get_unpublished(&key)) {
+    validate_sections(&news_path, news, options, problems);
+  }
+}
+
+/// Validate a single Markdown file.
+fn validate_file(path: &Path, content: &str, problems: &mut Vec<Problem>) {
+  let mut report = |offset: Option<usize>, message: String| {
+    problems.push(Problem {
+      path: path.to_owned(),
+      line: offset.map(|offset| line_of(content, offset)),
+      message,
+    })
+  };
+
+  if let Err(err) = News::parse_from_md(content) {
+    report(Some(0), err.to_string());
+    return;
+  }
+
+  let md = split_front_matter(content).map_or(content, |(_, md)| md);
+
+  // offsets reported by the parser are relative to the content after the front matter
+  let base = content.len() - md.len();
+
+  // a file with only a front matter is fine, e.g. to hold the metadata of a news split into several files
+  if content.trim().is_empty() {
+    report(None, "empty file".to_owned());
+    return;
+  }
+
+  let mut broken_links = Vec::new();
+  let mut callback = |link: BrokenLink| {
+    broken_links.push((link.span.start, link.reference.to_string()));
+    None
+  };
+  let parser = Parser::new_with_broken_link_callback(md, Options::all(), Some(&mut callback));
+
+  let mut previous_level = None;
+  let mut heading: Option<(HeadingLevel, usize, String)> = None;
+
+  for (event, range) in parser.into_offset_iter() {
+    let offset = base + range.start;
+
+    match event {
+      Event::Start(Tag::Heading(level, ..)) => heading = Some((level, offset, String::new())),
+
+      Event::Text(ref text) | Event::Code(ref text) => {
+        if let Some((_, _, ref mut heading_text)) = heading {
+          heading_text.push_str(text);
+        }
+      }
+
+      Event::End(Tag::Heading(..)) => {
+        if let Some((level, offset, text)) = heading.take() {
+          let level = level as usize;
+
+          if text.trim().is_empty() {
+            report(Some(offset), "empty heading".to_owned());
+          }
+
+          match previous_level {
+            Some(previous) if level > previous + 1 => report(
+              Some(offset),
+              format!("heading level jumps from h{} to h{}", previous, level),
+            ),
+            _ => (),
+          }
+
+          previous_level = Some(level);
+        }
+      }
+
+      Event::Start(Tag::Link(_, ref url, _)) | Event::Start(Tag::Image(_, ref url, _)) => {
+        if let Some(message) = check_url(url) {
+          report(Some(offset), message);
+        }
+      }
+
+      _ => (),
+    }
+  }
+
+  for (offset, reference) in broken_links {
+    report(
+      Some(base + offset),
+      format!("undefined link reference: {}", reference),
+    );
+  }
+}
+
+/// Check that the top-level sections of a news contain the required ones.
+fn validate_sections(
+  path: &Path,
+  news: &News,
+  options: &ValidationOptions,
+  problems: &mut Vec<Problem>,
+) {
+  for required in &options.required_sections {
+    let required_lowercase = required.to_lowercase();
+    let found = news.issue.sections.iter().any(|section| {
+      section.heading.level == 1
+        && section
+          .heading
+          .text
+          .to_lowercase()
+          .contains(&required_lowercase)
+    });
+
+    if !found {
+      problems.push(Problem {
+        path: path.to_owned(),
+        line: None,
+        message: format!("missing required section: {}", required),
+      });
+    }
+  }
+}
+
+/// Check a link destination, returning a message if it’s malformed.
+fn check_url(url: &str) -> Option<String> {
+  if url.trim().is_empty() {
+    return Some("empty link destination".to_owned());
+  }
+
+  if url.chars().any(char::is_whitespace) {
+    return Some(format!("link destination contains whitespace: {}", url));
+  }
+
+  let scheme = match url.split_once(':') {
+    Some((scheme, _)) if !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphabetic()) => {
+      scheme.to_ascii_lowercase()
+    }
+
+    // relative links, anchors, etc.
+    _ => return None,
+  };
+
+  match scheme.as_str() {
+    "http" | "https" => {
+      let host = url
+        .split_once("://")
+        .map(|(_, rest)| rest.split(['/', '?', '#']).next().unwrap_or(""));
+
+      match host {
+        Some(host) if !host.is_empty() && !host.starts_with('.') && !host.ends_with('.') => None,
+        _ => Some(format!("malformed URL: {}", url)),
+      }
+    }
+
+    "mailto" => None,
+
+    _ => Some(format!("unsupported URL scheme: {}", url)),
+  }
+}
+
+/// Line number, starting at 1, of an offset in some text.
+fn line_of(text: &str, offset: usize) -> usize {
+  text[..offset.min(text.len())].matches('\n').count() + 1
+}
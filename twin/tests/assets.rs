@@ -0,0 +1,56 @@
+use twin::assets::Assets;
+
+fn assets() -> Assets {
+  Assets::new(
+    "/2022/Jun/27/assets",
+    [
+      "logo.png".to_owned(),
+      "img/demo 1.gif".to_owned(),
+      "img/50% #1?.png".to_owned(),
+      "img/écran.png".to_owned(),
+    ],
+  )
+}
+
+#[test]
+fn test_url() {
+  let assets = assets();
+
+  assert_eq!(assets.url("logo.png"), "/2022/Jun/27/assets/logo.png");
+  assert_eq!(
+    assets.url("img/demo 1.gif"),
+    "/2022/Jun/27/assets/img/demo%201.gif"
+  );
+  assert_eq!(
+    assets.url("img/50% #1?.png"),
+    "/2022/Jun/27/assets/img/50%25%20%231%3F.png"
+  );
+  assert_eq!(
+    assets.url("img/écran.png"),
+    "/2022/Jun/27/assets/img/%C3%A9cran.png"
+  );
+}
+
+#[test]
+fn test_resolve() {
+  let assets = assets();
+
+  assert_eq!(
+    assets.resolve("", "img/demo%201.gif#top").as_deref(),
+    Some("/2022/Jun/27/assets/img/demo%201.gif#top")
+  );
+  assert_eq!(
+    assets.resolve("img", "50%25%20%231%3F.png").as_deref(),
+    Some("/2022/Jun/27/assets/img/50%25%20%231%3F.png")
+  );
+  assert_eq!(
+    assets.resolve("img", "%C3%A9cran.png").as_deref(),
+    Some("/2022/Jun/27/assets/img/%C3%A9cran.png")
+  );
+  assert_eq!(
+    assets.resolve("img", "écran.png").as_deref(),
+    Some("/2022/Jun/27/assets/img/%C3%A9cran.png")
+  );
+  assert_eq!(assets.resolve("", "img/50%.png"), None);
+  assert_eq!(assets.resolve("", "https://example.com/logo.png"), None);
+}
@@ -0,0 +1,100 @@
+use git2::{Repository, Signature, Time};
+use std::{fs, path::Path};
+use twin::{
+  git::GitRevision,
+  news::{Month, NewsKey, NewsStore},
+};
+
+fn key(year: u16, month: Month, day: u8) -> NewsKey {
+  NewsKey { year, month, day }
+}
+
+/// Commit the whole working tree at a given time (in seconds), returning the commit id.
+fn commit_all(repo: &Repository, time: i64, message: &str) -> String {
+  let mut index = repo.index().unwrap();
+  index
+    .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+    .unwrap();
+  index.update_all(["*"], None).unwrap();
+  index.write().unwrap();
+
+  let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+  let signature = Signature::new("twin", "twin@example.com", &Time::new(time, 0)).unwrap();
+  let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+  let parents: Vec<_> = parent.iter().collect();
+
+  repo
+    .commit(
+      Some("HEAD"),
+      &signature,
+      &signature,
+      message,
+      &tree,
+      &parents,
+    )
+    .unwrap()
+    .to_string()
+}
+
+fn write(root: &Path, path: &str, content: &str) {
+  let path = root.join(path);
+  fs::create_dir_all(path.parent().unwrap()).unwrap();
+  fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_news_from_git_revision() {
+  let dir = tempfile::tempdir().expect("temporary directory");
+  let repo = Repository::init(dir.path()).unwrap();
+  let root = dir.path().join("contents");
+
+  write(&root, "2022/Jun/06.md", "# First");
+  write(&root, "2022/Jun/13/00-intro.md", "![logo](logo.png)");
+  write(&root, "2022/Jun/13/logo.png", "png");
+  let first_commit = commit_all(&repo, 1_656_000_000, "first news");
+
+  write(&root, "2022/Jun/13/01-core.md", "# Core");
+  let second_commit = commit_all(&repo, 1_656_600_000, "core update");
+
+  // uncommitted changes are not read
+  write(&root, "2022/Jun/20.md", "# Third");
+
+  let git = GitRevision::new(&root, "HEAD").unwrap();
+  let mut store = NewsStore::new(&root).with_source(git);
+  store.populate_from_root().unwrap();
+
+  let first = key(2022, Month::Jun, 6);
+  let second = key(2022, Month::Jun, 13);
+  assert_eq!(
+    store.keys().copied().collect::<Vec<_>>(),
+    vec![first, second]
+  );
+  assert_eq!(store.source_version(), Some(second_commit.clone()));
+  assert!(store.get(&second).unwrap().html.contains("Core"));
+  assert_eq!(store.get(&first).unwrap().next, Some(second));
+  assert_eq!(
+    store.get(&first).unwrap().last_updated.unwrap().timestamp(),
+    1_656_000_000
+  );
+  assert_eq!(
+    store
+      .get(&second)
+      .unwrap()
+      .last_updated
+      .unwrap()
+      .timestamp(),
+    1_656_600_000
+  );
+  assert_eq!(
+    store.read_asset(&second, "logo.png").as_deref(),
+    Some(&b"png"[..])
+  );
+
+  // rolling back is only a matter of pointing to another revision
+  let git = GitRevision::new(&root, first_commit.as_str()).unwrap();
+  let mut store = NewsStore::new(&root).with_source(git);
+  store.populate_from_root().unwrap();
+
+  assert_eq!(store.source_version(), Some(first_commit));
+  assert!(!store.get(&second).unwrap().html.contains("Core"));
+}
@@ -0,0 +1,337 @@
+use chrono::NaiveDate;
+use twin::{
+  autolink::{AutolinkRule, Autolinks},
+  news::{News, NewsMetadata, ParseOptions},
+};
+
+#[test]
+fn test_toml_front_matter() {
+  let news = News::parse_from_md(
+    "+++\ntitle = \"TWiN #1\"\nnumber = 1\nauthors = [\"phaazon\"]\ntags = [\"lsp\"]\ndate = 2022-06-06\n+++\n# Core\n",
+  )
+  .unwrap();
+
+  assert_eq!(news.metadata.title.as_deref(), Some("TWiN #1"));
+  assert_eq!(news.metadata.number, Some(1));
+  assert_eq!(news.metadata.authors, vec!["phaazon".to_owned()]);
+  assert_eq!(news.metadata.tags, vec!["lsp".to_owned()]);
+  assert_eq!(news.metadata.date, NaiveDate::from_ymd_opt(2022, 6, 6));
+  assert!(news.html.starts_with(r#"<h1 id="core">Core</h1>"#));
+}
+
+#[test]
+fn test_yaml_front_matter() {
+  let news = News::parse_from_md(
+    "---\ntitle: \"TWiN #2\"\nsummary: A summary.\neditors:\n  - someone\ndate: 2022-06-13\n---\nHello\n",
+  )
+  .unwrap();
+
+  assert_eq!(news.metadata.title.as_deref(), Some("TWiN #2"));
+  assert_eq!(news.metadata.summary.as_deref(), Some("A summary."));
+  assert_eq!(news.metadata.editors, vec!["someone".to_owned()]);
+  assert_eq!(news.metadata.date, NaiveDate::from_ymd_opt(2022, 6, 13));
+  assert_eq!(news.html, "<p>Hello</p>\n");
+}
+
+#[test]
+fn test_no_front_matter() {
+  let news = News::parse_from_md("# Core\n\n---\n").unwrap();
+  assert_eq!(news.metadata, NewsMetadata::default());
+
+  assert!(News::parse_from_md("+++\ntitle = \"unterminated\"\n").is_err());
+
+  // a leading thematic break isn’t a front matter
+  let news = News::parse_from_md("---\n\n# Core\n").unwrap();
+  assert_eq!(news.metadata, NewsMetadata::default());
+  assert!(news.html.starts_with("<hr />"));
+
+  // neither is text between two thematic breaks that isn’t a YAML mapping
+  let news = News::parse_from_md("---\n\n# Core\n\nFaster startup.\n\n---\n\nMore.\n").unwrap();
+  assert_eq!(news.metadata, NewsMetadata::default());
+  assert_eq!(news.issue.sections[0].heading.text, "Core");
+  assert!(news
+    .html
+    .contains("<p>Faster startup.</p>\n<hr />\n<p>More.</p>"));
+
+  // but invalid front matters are still reported
+  assert!(News::parse_from_md("---\ntitle: [unclosed\n---\nHello\n").is_err());
+}
+
+const ISSUE: &str = r#"Intro paragraph.
+
+# Neovim core
+
+Core changes.
+
+## Nightly
+
+### Treesitter highlighting
+
+Something about [treesitter](https://github.com/nvim-treesitter/nvim-treesitter).
+
+#### Details
+
+More details. Thanks [@someone](https://github.com/someone)!
+
+# Plugins
+
+## New plugins
+
+### [hop.nvim](https://github.com/phaazon/hop.nvim)
+
+Neovim motions on speed!
+"#;
+
+#[test]
+fn test_issue_tree() {
+  let news = News::parse_from_md(ISSUE).unwrap();
+  let issue = &news.issue;
+
+  assert_eq!(issue.intro, "<p>Intro paragraph.</p>\n");
+  assert_eq!(issue.sections.len(), 2);
+
+  let core = &issue.sections[0];
+  assert_eq!(core.heading.text, "Neovim core");
+  assert_eq!(core.body, "<p>Core changes.</p>\n");
+  assert_eq!(core.subsections[0].heading.text, "Nightly");
+
+  let item = &core.subsections[0].items[0];
+  assert_eq!(item.heading.text, "Treesitter highlighting");
+  assert_eq!(item.links.len(), 2);
+  assert_eq!(item.contributor.as_ref().unwrap().name, "someone");
+  assert!(item.body.contains(r#"<h4 id="details">Details</h4>"#));
+
+  let plugin = &issue.sections[1].subsections[0].items[0];
+  assert_eq!(plugin.heading.text, "hop.nvim");
+  assert_eq!(plugin.links[0].url, "https://github.com/phaazon/hop.nvim");
+  assert!(plugin.contributor.is_none());
+
+  // rendering the tree yields the same HTML as rendering the Markdown directly, with anchors on headings
+  let mut html = String::new();
+  pulldown_cmark::html::push_html(
+    &mut html,
+    pulldown_cmark::Parser::new_ext(ISSUE, pulldown_cmark::Options::all()),
+  );
+  let html = html
+    .replace("<h1>Neovim core", r#"<h1 id="neovim-core">Neovim core"#)
+    .replace("<h2>Nightly", r#"<h2 id="nightly">Nightly"#)
+    .replace(
+      "<h3>Treesitter",
+      r#"<h3 id="treesitter-highlighting">Treesitter"#,
+    )
+    .replace("<h4>Details", r#"<h4 id="details">Details"#)
+    .replace("<h1>Plugins", r#"<h1 id="plugins">Plugins"#)
+    .replace("<h2>New plugins", r#"<h2 id="new-plugins">New plugins"#)
+    .replace("<h3><a", r#"<h3 id="hopnvim"><a"#);
+  assert_eq!(news.html, html);
+}
+
+#[test]
+fn test_nested_headings() {
+  let news =
+    News::parse_from_md("# Core\n\n> ## Quoted\n> Text.\n\n- ## Listed\n  More.\n\n## Nightly\n")
+      .unwrap();
+  let core = &news.issue.sections[0];
+
+  // headings in containers don’t split the issue
+  assert_eq!(core.subsections.len(), 1);
+  assert_eq!(core.subsections[0].heading.text, "Nightly");
+  assert_eq!(
+    core.body,
+    "<blockquote>\n<h2 id=\"quoted\">Quoted</h2>\n<p>Text.</p>\n</blockquote>\n<ul>\n<li><h2 id=\"listed\">Listed</h2>\nMore.</li>\n</ul>\n"
+  );
+}
+
+#[test]
+fn test_heading_anchors_and_toc() {
+  let news = News::parse_from_md(
+    "# Core\n## Nightly\n### Fix\n## Stable {#release}\n### Fix\n# Plugins!\n### Fix\n",
+  )
+  .unwrap();
+
+  let ids: Vec<_> = news
+    .toc
+    .iter()
+    .flat_map(|entry| {
+      std::iter::once(entry).chain(
+        entry
+          .children
+          .iter()
+          .flat_map(|child| std::iter::once(child).chain(child.children.iter())),
+      )
+    })
+    .map(|entry| entry.id.as_str())
+    .collect();
+
+  assert_eq!(
+    ids,
+    ["core", "nightly", "fix", "release", "fix-1", "plugins", "fix-2"]
+  );
+  assert_eq!(news.toc.len(), 2);
+  assert_eq!(news.toc[0].children.len(), 2);
+  assert_eq!(news.toc[1].title, "Plugins!");
+  assert_eq!(news.toc[1].children[0].level, 3);
+
+  let news = News::parse_from_md("# Core\n# Stable {#core}\n# Nightly {#core}\n").unwrap();
+  let ids: Vec<_> = news.toc.iter().map(|entry| entry.id.as_str()).collect();
+  assert_eq!(ids, ["core", "core-1", "core-2"]);
+}
+
+#[test]
+fn test_autolinks() {
+  let news = News::parse_from_md(
+    "Fixed #123 and neovim/neovim#456, see gh:phaazon/hop.nvim.\n\nNot #12a, a/b, Vim/Neovim, lua/nvim-tree, `#789` or [#1](https://example.com).\n\n```\n#42\n```\n",
+  )
+  .unwrap();
+
+  assert_eq!(
+    news.html,
+    concat!(
+      r#"<p>Fixed <a href="https://github.com/neovim/neovim/issues/123">#123</a> and "#,
+      r#"<a href="https://github.com/neovim/neovim/issues/456">neovim/neovim#456</a>, see "#,
+      r#"<a href="https://github.com/phaazon/hop.nvim">phaazon/hop.nvim</a>.</p>"#,
+      "\n",
+      r#"<p>Not #12a, a/b, Vim/Neovim, lua/nvim-tree, <code>#789</code> or <a href="https://example.com">#1</a>.</p>"#,
+      "\n<pre><code>#42\n</code></pre>\n",
+    )
+  );
+
+  // headings and issues of This Week in Neovim are not Neovim issues
+  let news =
+    News::parse_from_md("# Fixes #123\n\nSee TWiN #12 and This Week in Neovim #13.\n").unwrap();
+  assert_eq!(
+    news.html,
+    "<h1 id=\"fixes-123\">Fixes #123</h1>\n<p>See TWiN #12 and This Week in Neovim #13.</p>\n"
+  );
+
+  let options = ParseOptions {
+    autolinks: Autolinks::new(vec![AutolinkRule::new(
+      r":h (?P<tag>[\w-]+)",
+      "https://neovim.io/doc/user/helptag.html?tag=$tag",
+    )
+    .unwrap()]),
+    ..ParseOptions::default()
+  };
+  let news = News::parse_from_md_with("See :h lsp-config, #123.", &options).unwrap();
+
+  assert_eq!(
+    news.html,
+    r#"<p>See <a href="https://neovim.io/doc/user/helptag.html?tag=lsp-config">:h lsp-config</a>, #123.</p>"#
+      .to_owned()
+      + "\n"
+  );
+}
+
+#[test]
+fn test_code_highlighting() {
+  let md = "```lua title=init.lua\nlocal x = 1 < 2\n```\n\n```unknown\nfoo\n```\n";
+  let news = News::parse_from_md(md).unwrap();
+
+  assert!(news
+    .html
+    .starts_with(r#"<pre class="highlight" style="background-color:#2b303b;color:#c0c5ce;"><code class="language-lua"><span style="#));
+  assert!(news.html.contains("&lt;"));
+  assert!(news
+    .html
+    .ends_with("<pre><code class=\"language-unknown\">foo\n</code></pre>\n"));
+
+  for lang in ["vim", "sh", "toml", "rust"] {
+    let news = News::parse_from_md(format!("```{}\nx\n```\n", lang)).unwrap();
+    assert!(
+      news.html.starts_with(r#"<pre class="highlight""#),
+      "{}",
+      lang
+    );
+  }
+
+  let options = ParseOptions {
+    highlighter: None,
+    ..ParseOptions::default()
+  };
+  let news = News::parse_from_md_with(md, &options).unwrap();
+  assert!(news
+    .html
+    .starts_with("<pre><code class=\"language-lua\">local x = 1 &lt; 2\n</code></pre>"));
+}
+
+#[test]
+fn test_issue_parts() {
+  let news = News::parse_from_md(
+    r#"# Neovim core
+
+## Nightly
+
+### Treesitter highlighting {.treesitter}
+
+Faster.
+
+# Plugins
+
+### [hop.nvim](https://github.com/phaazon/hop.nvim)
+
+Neovim motions on speed!
+
+### nvim-treesitter {.treesitter}
+
+New parsers.
+
+# Guides {.treesitter}
+
+Parsing.
+"#,
+  )
+  .unwrap();
+  let issue = &news.issue;
+
+  let core = issue.sections_about("core");
+  assert_eq!(core.len(), 1);
+  assert_eq!(core[0].heading.text, "Neovim core");
+  assert_eq!(issue.sections_about("Plugins")[0].heading.id, "plugins");
+  assert!(issue.sections_about("nightly-core").is_empty());
+  assert_eq!(issue.sections_about("nightly")[0].heading.text, "Nightly");
+
+  assert!(issue.has_section_named("core"));
+  assert!(issue.has_section_named("Neovim-Core"));
+  assert!(issue.has_section_named("nightly"));
+  assert!(issue.has_section_named("treesitter"));
+  assert!(!issue.has_section_named("core-neovim"));
+  assert!(!issue.has_section_named("parsers"));
+
+  let parts = issue.parts_tagged("treesitter");
+  let headings: Vec<_> = parts
+    .iter()
+    .map(|part| part.heading().text.as_str())
+    .collect();
+  assert_eq!(
+    headings,
+    ["Treesitter highlighting", "nvim-treesitter", "Guides"]
+  );
+  assert_eq!(
+    parts[1].to_html(),
+    "<h3 id=\"nvim-treesitter\" class=\"treesitter\">nvim-treesitter</h3>\n<p>New parsers.</p>\n"
+  );
+}
+
+#[test]
+fn test_footnotes_across_sections() {
+  let news = News::parse_from_md(
+    "# Core\n\nFaster[^perf].\n\n# Plugins\n\nNew plugins[^plugins].\n\n[^perf]: Much faster.\n\n[^plugins]: Many.\n",
+  )
+  .unwrap();
+  let sections = &news.issue.sections;
+
+  // footnotes are numbered across the whole issue
+  assert!(sections[0].body.contains("<a href=\"#perf\">1</a>"));
+  assert!(sections[1].body.contains("<a href=\"#plugins\">2</a>"));
+  assert!(sections[1]
+    .body
+    .contains("<div class=\"footnote-definition\" id=\"perf\"><sup class=\"footnote-definition-label\">1</sup>"));
+  assert!(sections[1]
+    .body
+    .contains("<div class=\"footnote-definition\" id=\"plugins\"><sup class=\"footnote-definition-label\">2</sup>"));
+  assert_eq!(sections[0].heading.html, "Core");
+  assert!(news
+    .html
+    .starts_with("<h1 id=\"core\">Core</h1>\n<p>Faster"));
+  assert!(!news.html.contains('\0'));
+}
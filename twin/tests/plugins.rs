@@ -0,0 +1,89 @@
+use std::fs;
+use twin::{
+  news::{Month, NewsKey, NewsStore},
+  plugins::Repository,
+};
+
+fn key(year: u16, month: Month, day: u8) -> NewsKey {
+  NewsKey { year, month, day }
+}
+
+#[test]
+fn test_repository_from_url() {
+  assert_eq!(
+    Repository::from_url("https://github.com/Phaazon/hop.nvim"),
+    Some(Repository::new("phaazon", "hop.nvim"))
+  );
+  assert_eq!(
+    Repository::from_url("https://www.github.com/neovim/neovim/pull/42#issuecomment-1"),
+    Some(Repository::new("neovim", "neovim"))
+  );
+  assert_eq!(
+    Repository::from_url("http://github.com/owner/repo.git"),
+    Some(Repository::new("owner", "repo"))
+  );
+  assert_eq!(Repository::from_url("https://github.com/phaazon"), None);
+  assert_eq!(
+    Repository::from_url("https://github.com/sponsors/phaazon"),
+    None
+  );
+  assert_eq!(Repository::from_url("https://gitlab.com/owner/repo"), None);
+}
+
+#[test]
+fn test_plugin_index() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::write(
+    root.path().join("2022/Jun/06.md"),
+    "# Plugins\n\n### [hop.nvim](https://github.com/phaazon/hop.nvim)\n\nSee [the README](https://github.com/phaazon/hop.nvim#readme), by [@phaazon](https://github.com/phaazon).\n",
+  )
+  .unwrap();
+  fs::write(
+    root.path().join("2022/Jun/13.md"),
+    "Thanks to [hop](https://github.com/Phaazon/Hop.nvim)!\n\n# Core\n\nSee [#42](https://github.com/neovim/neovim/pull/42).\n",
+  )
+  .unwrap();
+
+  let mut store = NewsStore::load_from_root(root.path()).unwrap();
+  let index = store.plugin_index();
+
+  let repositories: Vec<_> = index
+    .repositories()
+    .map(|(repository, mentions)| (repository.to_string(), mentions.len()))
+    .collect();
+  assert_eq!(
+    repositories,
+    vec![
+      ("neovim/neovim".to_owned(), 1),
+      ("phaazon/hop.nvim".to_owned(), 2)
+    ]
+  );
+
+  let hop = Repository::new("phaazon", "hop.nvim");
+  let mentions: Vec<_> = index
+    .mentions(&hop)
+    .iter()
+    .map(|mention| {
+      (
+        mention.key,
+        mention.anchor.as_deref(),
+        mention.heading.as_str(),
+      )
+    })
+    .collect();
+  assert_eq!(
+    mentions,
+    vec![
+      (key(2022, Month::Jun, 6), Some("hopnvim"), "hop.nvim"),
+      (key(2022, Month::Jun, 13), None, ""),
+    ]
+  );
+
+  store.remove(key(2022, Month::Jun, 13));
+  assert_eq!(store.plugin_index().mentions(&hop).len(), 1);
+  assert!(store
+    .plugin_index()
+    .mentions(&Repository::new("neovim", "neovim"))
+    .is_empty());
+}
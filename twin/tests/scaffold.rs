@@ -0,0 +1,63 @@
+use chrono::NaiveDate;
+use std::fs;
+use twin::{
+  news::NewsStore,
+  numbers::IssueNumbers,
+  scaffold::{scaffold, Layout, ScaffoldError},
+  validate::{validate, ValidationOptions},
+};
+
+#[test]
+fn test_scaffold_next_news() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::write(root.path().join("2022/Jun/27.md"), "# Core\n# Plugins\n").unwrap();
+
+  let scaffolded = scaffold(
+    root.path(),
+    None,
+    Layout::Directory,
+    IssueNumbers::default(),
+  )
+  .unwrap();
+  assert_eq!(
+    scaffolded.key.to_date(),
+    NaiveDate::from_ymd_opt(2022, 7, 4)
+  );
+  assert_eq!(scaffolded.number, 2);
+  assert_eq!(scaffolded.files.len(), 3);
+
+  let scaffolded = scaffold(root.path(), None, Layout::File, IssueNumbers::default()).unwrap();
+  assert_eq!(
+    scaffolded.key.to_date(),
+    NaiveDate::from_ymd_opt(2022, 7, 11)
+  );
+  assert_eq!(scaffolded.number, 3);
+  assert_eq!(scaffolded.files, vec![root.path().join("2022/Jul/11.md")]);
+
+  // the scaffolded news are valid and keep their numbers
+  let problems = validate(root.path(), &ValidationOptions::default()).unwrap();
+  assert!(problems.is_empty(), "{:?}", problems);
+
+  let store = NewsStore::load_from_root(root.path()).unwrap();
+  let numbers: Vec<_> = store.keys().filter_map(|key| store.number(key)).collect();
+  assert_eq!(numbers, vec![1, 2, 3]);
+
+  // existing news are never overwritten
+  let date = NaiveDate::from_ymd_opt(2022, 6, 27);
+  assert!(matches!(
+    scaffold(root.path(), date, Layout::File, IssueNumbers::default()),
+    Err(ScaffoldError::AlreadyExists(_))
+  ));
+
+  // numbers already assigned by the backend are skipped, even for news not present anymore
+  let numbers_path = root.path().join("numbers.toml");
+  fs::write(
+    &numbers_path,
+    "[[issues]]\nnumber = 7\n[issues.key]\nyear = 2022\nmonth = \"Jun\"\nday = 20\n",
+  )
+  .unwrap();
+  let numbers = IssueNumbers::load(&numbers_path).unwrap();
+  let scaffolded = scaffold(root.path(), None, Layout::File, numbers).unwrap();
+  assert_eq!(scaffolded.number, 8);
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use twin::news::{Month, NewsKey, NewsStore};
+
+fn key(year: u16, month: Month, day: u8) -> NewsKey {
+  NewsKey { year, month, day }
+}
+
+#[test]
+fn test_search() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::write(
+    root.path().join("2022/Jun/06.md"),
+    "Hello &amp; welcome!\n\n# Plugins\n\n### telescope.nvim\n\nA fuzzy finder, by [@someone](https://github.com/someone).\n",
+  )
+  .unwrap();
+  fs::write(
+    root.path().join("2022/Jun/13.md"),
+    "# Core\n\n### Fuzzy matching\n\nThe `telescope` picker now uses a **faster** fuzzy matcher.\n",
+  )
+  .unwrap();
+
+  let mut store = NewsStore::load_from_root(root.path()).unwrap();
+  let results = store.search_index().search("Telescope FUZZY", 10);
+
+  let found: Vec<_> = results
+    .iter()
+    .map(|result| (result.key, result.anchor.as_deref()))
+    .collect();
+  assert_eq!(
+    found,
+    vec![
+      (key(2022, Month::Jun, 13), Some("fuzzy-matching")),
+      (key(2022, Month::Jun, 6), Some("telescopenvim")),
+    ]
+  );
+
+  let snippet = &results[0].snippet;
+  assert_eq!(
+    snippet.text,
+    "The telescope picker now uses a faster fuzzy matcher."
+  );
+  let highlighted: Vec<_> = snippet
+    .highlights
+    .iter()
+    .map(|range| &snippet.text[range.clone()])
+    .collect();
+  assert_eq!(highlighted, vec!["telescope", "fuzzy"]);
+
+  // prefixes match longer words
+  let results = store.search_index().search("welc", 10);
+  assert_eq!(results.len(), 1);
+  assert_eq!(results[0].anchor, None);
+  assert_eq!(results[0].snippet.text, "Hello & welcome!");
+
+  // updates are reflected in the index
+  fs::write(root.path().join("2022/Jun/13.md"), "# Core\n\nNothing.\n").unwrap();
+  store.update(key(2022, Month::Jun, 13)).unwrap();
+  assert_eq!(store.search_index().search("telescope fuzzy", 10).len(), 1);
+
+  store.remove(key(2022, Month::Jun, 6));
+  assert!(store.search_index().search("telescope", 10).is_empty());
+  assert!(store.search_index().search("", 10).is_empty());
+}
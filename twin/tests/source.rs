@@ -0,0 +1,113 @@
+use twin::news::{
+  LoadMode, MemorySource, Month, NewsFiles, NewsKey, NewsSource, NewsStore, TarSource,
+};
+
+fn key(year: u16, month: Month, day: u8) -> NewsKey {
+  NewsKey { year, month, day }
+}
+
+fn contents() -> MemorySource {
+  MemorySource::new()
+    .with_file("2022/Jun/06.md", "# First")
+    .with_file("2022/Jun/13/01-plugins.md", "# Plugins")
+    .with_file("2022/Jun/13/00-core.md", "![logo](img/logo.png)")
+    .with_file("2022/Jun/13/img/logo.png", "png")
+    .with_file("2022/June/20.md", "# Misplaced")
+    .with_file("README.md", "Not a news")
+}
+
+#[test]
+fn test_memory_source() {
+  let source = contents();
+
+  assert_eq!(
+    source.read(key(2022, Month::Jun, 13)).unwrap(),
+    Some(NewsFiles::Dir {
+      mds: vec![
+        ("00-core.md".to_owned(), "![logo](img/logo.png)".to_owned()),
+        ("01-plugins.md".to_owned(), "# Plugins".to_owned()),
+      ],
+      assets: vec!["img/logo.png".to_owned()],
+    })
+  );
+  assert_eq!(source.read(key(2022, Month::Jun, 20)).unwrap(), None);
+
+  let mut store = NewsStore::new("contents")
+    .with_load_mode(LoadMode::Lenient)
+    .with_source(source);
+  store.populate_from_root().unwrap();
+
+  let first = key(2022, Month::Jun, 6);
+  let second = key(2022, Month::Jun, 13);
+  assert_eq!(
+    store.keys().copied().collect::<Vec<_>>(),
+    vec![first, second]
+  );
+  assert_eq!(store.get(&first).unwrap().next, Some(second));
+  assert!(store
+    .get(&second)
+    .unwrap()
+    .html
+    .contains(r#"<img src="/2022/Jun/13/assets/img/logo.png" alt="logo" />"#));
+  assert_eq!(
+    store.read_asset(&second, "img/logo.png").as_deref(),
+    Some(&b"png"[..])
+  );
+
+  let failures: Vec<_> = store
+    .report()
+    .failures
+    .iter()
+    .map(|failure| (failure.path.clone(), failure.error.kind()))
+    .collect();
+  assert_eq!(
+    failures,
+    vec![("contents/2022/June".into(), "CannotParseMonth")]
+  );
+}
+
+#[test]
+fn test_tar_source() {
+  let mut builder = tar::Builder::new(Vec::new());
+
+  for (path, content) in [
+    ("./2022/Jun/06.md", "# First"),
+    ("./2022/Jun/13/00-core.md", "# Core"),
+  ] {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+      .append_data(&mut header, path, content.as_bytes())
+      .unwrap();
+  }
+
+  let archive = builder.into_inner().unwrap();
+  let source = TarSource::from_reader(archive.as_slice()).unwrap();
+  let store = {
+    let mut store = NewsStore::new("contents").with_source(source);
+    store.populate_from_root().unwrap();
+    store
+  };
+
+  assert_eq!(store.keys().count(), 2);
+  assert!(store
+    .get(&key(2022, Month::Jun, 13))
+    .unwrap()
+    .html
+    .contains("Core"));
+
+  // an archive can be opened as the root of a store, and is read again when repopulating it
+  let dir = tempfile::tempdir().expect("temporary directory");
+  let path = dir.path().join("contents.tar");
+  std::fs::write(&path, &archive).unwrap();
+
+  let mut store = NewsStore::open(&path).unwrap();
+  store.populate_from_root().unwrap();
+  assert_eq!(store.keys().count(), 2);
+
+  std::fs::write(&path, b"").unwrap();
+  store.populate_from_root().unwrap();
+  assert_eq!(store.keys().count(), 0);
+}
@@ -0,0 +1,556 @@
+use std::fs;
+use twin::{
+  highlight::Highlighter,
+  news::{LoadMode, Month, NewsError, NewsKey, NewsState, NewsStore, ParseOptions},
+  numbers::IssueNumbers,
+};
+
+fn key(year: u16, month: Month, day: u8) -> NewsKey {
+  NewsKey { year, month, day }
+}
+
+#[test]
+fn test_key_from_path() {
+  let store = NewsStore::new("contents");
+
+  assert_eq!(
+    store.key_from_path("contents/2022/Jun/06.md"),
+    Some(key(2022, Month::Jun, 6))
+  );
+  assert_eq!(
+    store.key_from_path("contents/2022/Jun/27"),
+    Some(key(2022, Month::Jun, 27))
+  );
+  assert_eq!(
+    store.key_from_path("contents/2022/Jul/04/plugins/00-new.md"),
+    Some(key(2022, Month::Jul, 4))
+  );
+  assert_eq!(store.key_from_path("contents/2022/Jun"), None);
+  assert_eq!(store.key_from_path("contents/2022/Jun/6.md"), None);
+  assert_eq!(store.key_from_path("elsewhere/2022/Jun/06.md"), None);
+}
+
+#[test]
+fn test_update_single_news() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun/13")).unwrap();
+  fs::write(root.path().join("2022/Jun/06.md"), "# First").unwrap();
+  fs::write(root.path().join("2022/Jun/13/00-core.md"), "# Second").unwrap();
+
+  let mut store = NewsStore::new(root.path());
+  store.populate_from_root().unwrap();
+
+  let first = key(2022, Month::Jun, 6);
+  let second = key(2022, Month::Jun, 13);
+  let third = key(2022, Month::Jun, 20);
+  assert_eq!(store.get(&first).unwrap().next, Some(second));
+
+  fs::write(root.path().join("2022/Jun/20.md"), "# Third").unwrap();
+  let path = root.path().join("2022/Jun/20.md");
+  store.update(store.key_from_path(path).unwrap()).unwrap();
+
+  assert!(store.get(&third).unwrap().html.contains("Third"));
+  assert_eq!(store.get(&third).unwrap().prev, Some(second));
+  assert_eq!(store.get(&second).unwrap().next, Some(third));
+  assert_eq!(store.neighbours(&second), (Some(first), Some(third)));
+}
+
+#[test]
+fn test_remove_and_rename_news() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::write(root.path().join("2022/Jun/06.md"), "# First").unwrap();
+  fs::write(root.path().join("2022/Jun/13.md"), "# Second").unwrap();
+  fs::write(root.path().join("2022/Jun/20.md"), "# Third").unwrap();
+
+  let mut store = NewsStore::new(root.path());
+  store.populate_from_root().unwrap();
+
+  let first = key(2022, Month::Jun, 6);
+  let second = key(2022, Month::Jun, 13);
+  let third = key(2022, Month::Jun, 20);
+  let fourth = key(2022, Month::Jun, 27);
+
+  fs::remove_file(root.path().join("2022/Jun/13.md")).unwrap();
+  store.refresh(second).unwrap();
+
+  assert!(store.get(&second).is_none());
+  assert_eq!(store.get(&first).unwrap().next, Some(third));
+  assert_eq!(store.get(&third).unwrap().prev, Some(first));
+
+  fs::rename(
+    root.path().join("2022/Jun/20.md"),
+    root.path().join("2022/Jun/27.md"),
+  )
+  .unwrap();
+  store.rename(third, fourth).unwrap();
+
+  assert!(store.get(&third).is_none());
+  assert!(store.get(&fourth).unwrap().html.contains("Third"));
+  assert_eq!(store.get(&first).unwrap().next, Some(fourth));
+  assert_eq!(store.keys().count(), 2);
+}
+
+#[test]
+fn test_stable_issue_numbers() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  let numbers_path = root.path().join("numbers.toml");
+  let news_root = root.path().join("contents");
+  fs::create_dir_all(news_root.join("2022/Jun")).unwrap();
+  fs::write(news_root.join("2022/Jun/13.md"), "# First").unwrap();
+  fs::write(news_root.join("2022/Jun/20.md"), "# Second").unwrap();
+
+  let mut store =
+    NewsStore::new(&news_root).with_numbers(IssueNumbers::load(&numbers_path).unwrap());
+  store.populate_from_root().unwrap();
+
+  assert_eq!(store.number(&key(2022, Month::Jun, 13)), Some(1));
+  assert_eq!(store.number(&key(2022, Month::Jun, 20)), Some(2));
+
+  // a back-dated news doesn’t renumber the others
+  fs::write(news_root.join("2022/Jun/06.md"), "# Back-dated").unwrap();
+  store.update(key(2022, Month::Jun, 6)).unwrap();
+
+  assert_eq!(store.number(&key(2022, Month::Jun, 6)), Some(3));
+  assert_eq!(store.number(&key(2022, Month::Jun, 13)), Some(1));
+  assert_eq!(store.key_from_number(2), Some(key(2022, Month::Jun, 20)));
+  store.save_numbers().unwrap();
+
+  // numbers are persisted and metadata overrides them
+  fs::write(
+    news_root.join("2022/Jun/27.md"),
+    "+++\nnumber = 42\n+++\n# Fourth",
+  )
+  .unwrap();
+  let store = NewsStore::load_from_root(&news_root).unwrap();
+  assert_eq!(store.number(&key(2022, Month::Jun, 27)), Some(42));
+
+  let mut store =
+    NewsStore::new(&news_root).with_numbers(IssueNumbers::load(&numbers_path).unwrap());
+  store.populate_from_root().unwrap();
+  assert_eq!(store.number(&key(2022, Month::Jun, 6)), Some(3));
+  assert_eq!(store.key_from_number(42), Some(key(2022, Month::Jun, 27)));
+}
+
+#[test]
+fn test_numbers_follow_previous_news() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::create_dir_all(root.path().join("2022/Jul")).unwrap();
+  fs::write(root.path().join("2022/Jun/13.md"), "# First").unwrap();
+  fs::write(root.path().join("2022/Jun/20.md"), "# Second").unwrap();
+  fs::write(
+    root.path().join("2022/Jun/27.md"),
+    "+++\nnumber = 42\n+++\n# Third",
+  )
+  .unwrap();
+  fs::write(root.path().join("2022/Jul/04.md"), "# Fourth").unwrap();
+
+  // a number set in the metadata of a news doesn’t push the numbers of the news before it
+  let store = NewsStore::load_from_root(root.path()).unwrap();
+  let numbers: Vec<_> = store.keys().filter_map(|key| store.number(key)).collect();
+  assert_eq!(numbers, vec![1, 2, 42, 43]);
+}
+
+#[test]
+fn test_numbers_of_broken_news() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::write(root.path().join("2022/Jun/06.md"), "# First").unwrap();
+  fs::write(root.path().join("2022/Jun/13.md"), "+++\ntitle =\n+++\n").unwrap();
+  fs::write(root.path().join("2022/Jun/20.md"), "# Third").unwrap();
+
+  // a news that fails to load keeps its number, so that the next ones are not renumbered once it is fixed
+  let mut store = NewsStore::new(root.path()).with_load_mode(LoadMode::Lenient);
+  store.populate_from_root().unwrap();
+  assert_eq!(store.number(&key(2022, Month::Jun, 20)), Some(3));
+
+  fs::write(root.path().join("2022/Jun/13.md"), "# Second").unwrap();
+  store.refresh(key(2022, Month::Jun, 13)).unwrap();
+  let numbers: Vec<_> = store.keys().filter_map(|key| store.number(key)).collect();
+  assert_eq!(numbers, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_numbers_saved_on_publication() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  let numbers_path = root.path().join("numbers.toml");
+  let news_root = root.path().join("contents");
+  fs::create_dir_all(news_root.join("2022/Jun")).unwrap();
+  fs::write(news_root.join("2022/Jun/13.md"), "# First").unwrap();
+
+  let state = NewsState::from_store(
+    NewsStore::new(&news_root).with_numbers(IssueNumbers::load(&numbers_path).unwrap()),
+  );
+
+  // an update that fails is never published, so its numbers are not saved
+  let result = state.update(|store| {
+    store.populate_from_root()?;
+    Err(NewsError::CannotReadRevision("aborted".to_owned()))
+  });
+  assert!(result.is_err());
+  assert!(!numbers_path.exists());
+
+  state.reload().unwrap();
+  let numbers = IssueNumbers::load(&numbers_path).unwrap();
+  assert_eq!(numbers.get(&key(2022, Month::Jun, 13)), Some(1));
+}
+
+#[test]
+fn test_lenient_loading() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::create_dir_all(root.path().join("2022/June")).unwrap();
+  fs::write(root.path().join("2022/Jun/06.md"), "# First").unwrap();
+  fs::write(root.path().join("2022/Jun/7.md"), "# Badly named").unwrap();
+  fs::write(root.path().join("2022/Jun/13.md"), "+++\ntitle =\n+++\n").unwrap();
+  fs::write(root.path().join("2022/Jun/20.md"), "# Third").unwrap();
+
+  assert!(NewsStore::load_from_root(root.path()).is_err());
+
+  let mut store = NewsStore::new(root.path()).with_load_mode(LoadMode::Lenient);
+  store.populate_from_root().unwrap();
+
+  assert_eq!(store.keys().count(), 2);
+  assert_eq!(
+    store.get(&key(2022, Month::Jun, 6)).unwrap().next,
+    Some(key(2022, Month::Jun, 20))
+  );
+
+  let mut failures: Vec<_> = store
+    .report()
+    .failures
+    .iter()
+    .map(|failure| {
+      (
+        failure.path.strip_prefix(root.path()).unwrap().to_owned(),
+        failure.error.kind(),
+      )
+    })
+    .collect();
+  failures.sort();
+
+  assert_eq!(
+    failures,
+    vec![
+      ("2022/Jun/13.md".into(), "CannotParseFrontMatter"),
+      ("2022/Jun/7.md".into(), "CannotParseDay"),
+      ("2022/June".into(), "CannotParseMonth"),
+    ]
+  );
+
+  // fixing a news clears its failure
+  fs::write(root.path().join("2022/Jun/13.md"), "# Second").unwrap();
+  store.refresh(key(2022, Month::Jun, 13)).unwrap();
+  assert_eq!(store.report().failures.len(), 2);
+  assert_eq!(store.keys().count(), 3);
+}
+
+#[test]
+fn test_lenient_reload_keeps_previous_news() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::write(root.path().join("2022/Jun/06.md"), "# First").unwrap();
+  fs::write(root.path().join("2022/Jun/13.md"), "# Second").unwrap();
+
+  let state = NewsState::from_store(NewsStore::new(root.path()).with_load_mode(LoadMode::Lenient));
+  state.reload().unwrap();
+
+  // a news broken by a later change is still served in its previous version
+  fs::write(
+    root.path().join("2022/Jun/13.md"),
+    "+++\ntitle =\n+++\n# Broken",
+  )
+  .unwrap();
+  let store = state.reload().unwrap();
+
+  let second = key(2022, Month::Jun, 13);
+  assert_eq!(store.keys().count(), 2);
+  assert!(store.get(&second).unwrap().html.contains("Second"));
+  assert_eq!(
+    store.get(&key(2022, Month::Jun, 6)).unwrap().next,
+    Some(second)
+  );
+  assert_eq!(store.report().failures.len(), 1);
+}
+
+#[test]
+fn test_generations_share_news() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::write(root.path().join("2022/Jun/06.md"), "# First").unwrap();
+  fs::write(root.path().join("2022/Jun/13.md"), "# Second").unwrap();
+  fs::write(root.path().join("2022/Jun/20.md"), "# Third").unwrap();
+
+  let state = NewsState::from_store(NewsStore::new(root.path()));
+  let first = key(2022, Month::Jun, 6);
+  let second = key(2022, Month::Jun, 13);
+  let third = key(2022, Month::Jun, 20);
+  let before = state.reload().unwrap();
+
+  // news that didn’t change are not copied, neither by a reload nor by an update
+  let reloaded = state.reload().unwrap();
+  for key in before.keys() {
+    assert!(std::ptr::eq(
+      before.get(key).unwrap(),
+      reloaded.get(key).unwrap()
+    ));
+  }
+
+  fs::write(root.path().join("2022/Jun/20.md"), "# Third, fixed").unwrap();
+  let updated = state.update(|store| store.refresh(third)).unwrap();
+  assert!(std::ptr::eq(
+    before.get(&first).unwrap(),
+    updated.get(&first).unwrap()
+  ));
+  assert!(std::ptr::eq(
+    before.get(&second).unwrap(),
+    updated.get(&second).unwrap()
+  ));
+  assert!(updated.get(&third).unwrap().html.contains("fixed"));
+  assert!(before.get(&third).unwrap().html.contains("Third</h1>"));
+}
+
+#[test]
+fn test_news_assets() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  let dir = root.path().join("2022/Jun/27");
+  fs::create_dir_all(dir.join("plugins/img")).unwrap();
+  fs::write(
+    dir.join("00-intro.md"),
+    "![logo](logo.png) [missing](missing.png)",
+  )
+  .unwrap();
+  fs::write(
+    dir.join("plugins/00-new.md"),
+    "![demo](img/demo%201.gif) [logo](../logo.png#top) [site](https://example.com/logo.png)",
+  )
+  .unwrap();
+  fs::write(dir.join("logo.png"), "png").unwrap();
+  fs::write(dir.join("plugins/img/demo 1.gif"), "gif").unwrap();
+  fs::write(dir.join(".DS_Store"), "").unwrap();
+
+  let store = NewsStore::load_from_root(root.path()).unwrap();
+  let key = key(2022, Month::Jun, 27);
+  let news = store.get(&key).unwrap();
+
+  assert_eq!(
+    news.assets.paths().collect::<Vec<_>>(),
+    vec!["logo.png", "plugins/img/demo 1.gif"]
+  );
+  assert!(news
+    .html
+    .contains(r#"<img src="/2022/Jun/27/assets/logo.png" alt="logo" />"#));
+  assert!(news.html.contains(r#"<a href="missing.png">"#));
+  assert!(news
+    .html
+    .contains(r#"<img src="/2022/Jun/27/assets/plugins/img/demo%201.gif" alt="demo" />"#));
+  assert!(news
+    .html
+    .contains(r##"<a href="/2022/Jun/27/assets/logo.png#top">"##));
+  assert!(news
+    .html
+    .contains(r#"<a href="https://example.com/logo.png">"#));
+
+  let files = store.files(key).unwrap();
+  assert_eq!(files.len(), 2);
+  assert_eq!(files[1].0, dir.join("plugins/00-new.md"));
+  assert_eq!(
+    store.read_asset(&key, "logo.png").as_deref(),
+    Some(&b"png"[..])
+  );
+  assert_eq!(store.read_asset(&key, "00-intro.md"), None);
+  assert_eq!(store.read_asset(&key, "../27/logo.png"), None);
+}
+
+#[test]
+fn test_news_assets_under_another_url() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  let dir = root.path().join("2022/Jun/27");
+  fs::create_dir_all(&dir).unwrap();
+  fs::write(
+    dir.join("00-intro.md"),
+    "![logo](logo.png) `<img src=\"/2022/Jun/27/assets/logo.png\">`",
+  )
+  .unwrap();
+  fs::write(dir.join("logo.png"), "png").unwrap();
+  fs::write(root.path().join("2022/Jun/20.md"), "# Previous").unwrap();
+
+  let store = NewsStore::load_from_root(root.path()).unwrap();
+  let key = key(2022, Month::Jun, 27);
+  let news = store
+    .reparse_with_assets_url(key, "/preview/2022/Jun/27/assets")
+    .unwrap()
+    .unwrap();
+
+  assert!(news
+    .html
+    .contains(r#"<img src="/preview/2022/Jun/27/assets/logo.png" alt="logo" />"#));
+  assert!(news
+    .html
+    .contains("<code>&lt;img src=&quot;/2022/Jun/27/assets/logo.png&quot;&gt;</code>"));
+  assert_eq!(news.prev, Some(self::key(2022, Month::Jun, 20)));
+  assert!(store
+    .get(&key)
+    .unwrap()
+    .html
+    .contains(r#"<img src="/2022/Jun/27/assets/logo.png" alt="logo" />"#));
+  assert_eq!(
+    store
+      .reparse_with_assets_url(self::key(2022, Month::Jun, 13), "/preview")
+      .unwrap(),
+    None
+  );
+}
+
+#[test]
+fn test_drafts_and_scheduled_news() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::write(root.path().join("2022/Jun/06.md"), "# First").unwrap();
+  fs::write(
+    root.path().join("2022/Jun/13.md"),
+    "+++\ndraft = true\n+++\n# Draft",
+  )
+  .unwrap();
+  fs::write(
+    root.path().join("2022/Jun/20.md"),
+    "+++\npublish_at = \"2999-06-20T09:00:00+02:00\"\n+++\n# Scheduled",
+  )
+  .unwrap();
+
+  let mut store = NewsStore::new(root.path());
+  store.populate_from_root().unwrap();
+
+  let first = key(2022, Month::Jun, 6);
+  let draft = key(2022, Month::Jun, 13);
+  let scheduled = key(2022, Month::Jun, 20);
+  assert_eq!(store.keys().copied().collect::<Vec<_>>(), vec![first]);
+  assert_eq!(
+    store.unpublished_keys().copied().collect::<Vec<_>>(),
+    vec![draft, scheduled]
+  );
+  assert!(store.get(&draft).is_none());
+  assert!(store
+    .get_unpublished(&draft)
+    .unwrap()
+    .html
+    .contains("Draft"));
+  assert_eq!(store.get(&first).unwrap().next, None);
+  assert!(store.search_index().search("scheduled", 10).is_empty());
+
+  let publish_at = store.next_publication().unwrap();
+  assert_eq!(publish_at.to_rfc3339(), "2999-06-20T07:00:00+00:00");
+  assert!(store
+    .publish_due(publish_at - chrono::Duration::seconds(1))
+    .is_empty());
+  assert_eq!(store.publish_due(publish_at), vec![scheduled]);
+
+  assert_eq!(store.get(&first).unwrap().next, Some(scheduled));
+  assert_eq!(store.get(&scheduled).unwrap().prev, Some(first));
+  assert_eq!(store.next_publication(), None);
+  assert_eq!(store.search_index().search("scheduled", 10).len(), 1);
+
+  // turning a published news into a draft hides it again
+  fs::write(
+    root.path().join("2022/Jun/06.md"),
+    "+++\ndraft = true\n+++\n# First",
+  )
+  .unwrap();
+  store.update(first).unwrap();
+
+  assert!(store.get(&first).is_none());
+  assert_eq!(store.get(&scheduled).unwrap().prev, None);
+}
+
+#[test]
+fn test_snapshot() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  let snapshot_path = root.path().join("snapshot.json");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::write(root.path().join("2022/Jun/06.md"), "# First").unwrap();
+  fs::write(root.path().join("2022/Jun/13.md"), "# Second").unwrap();
+
+  // snapshots are saved when publishing a generation
+  let state = NewsState::from_store(NewsStore::new(root.path()).with_snapshot(&snapshot_path));
+  state.reload().unwrap();
+
+  let first = key(2022, Month::Jun, 6);
+  let second = key(2022, Month::Jun, 13);
+
+  // tamper with the snapshot to see which news are parsed again
+  let snapshot = fs::read_to_string(&snapshot_path).unwrap();
+  fs::write(&snapshot_path, snapshot.replace("First", "Cached")).unwrap();
+  fs::write(root.path().join("2022/Jun/13.md"), "# Changed").unwrap();
+
+  let mut store = NewsStore::new(root.path()).with_snapshot(&snapshot_path);
+  assert!(store.restore_snapshot().unwrap());
+  assert_eq!(
+    store.keys().copied().collect::<Vec<_>>(),
+    vec![first, second]
+  );
+  assert_eq!(store.get(&first).unwrap().next, Some(second));
+  assert!(store.get(&second).unwrap().html.contains("Second"));
+
+  store.populate_from_root().unwrap();
+  assert!(store.get(&first).unwrap().html.contains("Cached"));
+  assert!(store.get(&second).unwrap().html.contains("Changed"));
+
+  // news parsed with other options are not restored
+  let highlighter = Highlighter::new("InspiredGitHub").unwrap();
+  let mut store = NewsStore::new(root.path())
+    .with_parse_options(ParseOptions {
+      highlighter: Some(highlighter),
+      ..ParseOptions::default()
+    })
+    .with_snapshot(&snapshot_path);
+  assert!(!store.restore_snapshot().unwrap());
+  assert!(store.get(&first).is_none());
+}
+
+#[test]
+fn test_parallel_population_is_deterministic() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Jun")).unwrap();
+  fs::create_dir_all(root.path().join("2022/Jul")).unwrap();
+
+  for day in 1..=30 {
+    let md = format!("# News {}\n\n```rust\nfn main() {{}}\n```", day);
+    fs::write(root.path().join(format!("2022/Jun/{:02}.md", day)), &md).unwrap();
+  }
+
+  for day in 1..=3 {
+    let md = "---\nnot: [valid\n---\n# Broken";
+    fs::write(root.path().join(format!("2022/Jul/{:02}.md", day)), md).unwrap();
+  }
+
+  let populate = || {
+    let mut store = NewsStore::new(root.path()).with_load_mode(LoadMode::Lenient);
+    store.populate_from_root().unwrap();
+    store
+  };
+  let store = populate();
+  let other = populate();
+
+  let keys: Vec<_> = store.keys().copied().collect();
+  assert_eq!(keys.len(), 30);
+  assert_eq!(keys, other.keys().copied().collect::<Vec<_>>());
+
+  for (i, key) in keys.iter().enumerate() {
+    let news = store.get(key).unwrap();
+    assert_eq!(news, other.get(key).unwrap());
+    assert_eq!(news.prev, i.checked_sub(1).map(|i| keys[i]));
+    assert_eq!(news.next, keys.get(i + 1).copied());
+  }
+
+  let paths = |store: &NewsStore| -> Vec<_> {
+    store
+      .report()
+      .failures
+      .iter()
+      .map(|failure| failure.path.clone())
+      .collect()
+  };
+  assert_eq!(paths(&store).len(), 3);
+  assert_eq!(paths(&store), paths(&other));
+}
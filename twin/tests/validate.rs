@@ -0,0 +1,65 @@
+use std::fs;
+use twin::validate::{validate, ValidationOptions};
+
+#[test]
+fn test_validate_contents() {
+  let root = tempfile::tempdir().expect("temporary directory");
+  fs::create_dir_all(root.path().join("2022/Feb")).unwrap();
+  fs::create_dir_all(root.path().join("2022/Jun/13")).unwrap();
+  fs::write(
+    root.path().join("2022/Feb/31.md"),
+    "# Core\n\n### Skipped level\n\n[bad](http://)\n\n# Plugins\n",
+  )
+  .unwrap();
+  fs::write(root.path().join("2022/Jun/6.md"), "# Core\n# Plugins\n").unwrap();
+  fs::write(
+    root.path().join("2022/Jun/13/00-core.md"),
+    "+++\ntitle = \"ok\"\n+++\n# Core\n\n[undefined][ref]\n",
+  )
+  .unwrap();
+
+  let problems: Vec<_> = validate(root.path(), &ValidationOptions::default())
+    .unwrap()
+    .into_iter()
+    .map(|problem| {
+      let path = problem.path.strip_prefix(root.path()).unwrap().to_owned();
+      (path.display().to_string(), problem.line, problem.message)
+    })
+    .collect();
+
+  assert_eq!(
+    problems,
+    vec![
+      (
+        "2022/Feb/31.md".to_owned(),
+        None,
+        "impossible date: 31 Feb 2022".to_owned()
+      ),
+      (
+        "2022/Feb/31.md".to_owned(),
+        Some(3),
+        "heading level jumps from h1 to h3".to_owned()
+      ),
+      (
+        "2022/Feb/31.md".to_owned(),
+        Some(5),
+        "malformed URL: http://".to_owned()
+      ),
+      (
+        "2022/Jun/13/00-core.md".to_owned(),
+        None,
+        "missing required section: plugin".to_owned()
+      ),
+      (
+        "2022/Jun/13/00-core.md".to_owned(),
+        Some(6),
+        "undefined link reference: ref".to_owned()
+      ),
+      (
+        "2022/Jun/6.md".to_owned(),
+        None,
+        "cannot parse day file: 6.md".to_owned()
+      ),
+    ]
+  );
+}